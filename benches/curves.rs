@@ -0,0 +1,90 @@
+// times the curve math and the full remap, run with cargo bench --bench curves
+// cargo bench --bench curves -- --save-baseline before records the current numbers
+// cargo bench --bench curves -- --baseline before compares against them
+// baseline.txt has the numbers for the current hilbert code under the same bench ids,
+// rerecord it when renaming or adding a bench
+// hilbertify runs the curve on rayons pool with --features rayon, so the serial and parallel
+// remaps get compared by saving a baseline without the feature and benching against it with it
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use sdl2::pixels::Color;
+
+use imagedisplay::{
+    Pos2,
+    curve::{HilbertCurve, SpaceFillingCurve},
+    config::CurveType,
+    image::{Image, PixelFormat}
+};
+
+
+const SIZE: usize = 1024;
+
+fn test_image(size: usize) -> Image
+{
+    let mut image = Image::new(size, size, PixelFormat::Rgb, Color::RGB(0, 0, 0));
+    image.data.iter_mut().enumerate().for_each(|(i, c)|
+    {
+        *c = Color::RGB(i as u8, (i >> 8) as u8, (i >> 16) as u8);
+    });
+
+    image
+}
+
+fn curves(c: &mut Criterion)
+{
+    let curve = HilbertCurve::new(SIZE).unwrap();
+
+    c.bench_function("hilbert_point_to_value", |b|
+    {
+        b.iter(||
+        {
+            (0..SIZE).for_each(|y|
+            {
+                (0..SIZE).for_each(|x|
+                {
+                    black_box(curve.point_to_value(black_box(Pos2{x, y})));
+                });
+            });
+        })
+    });
+
+    c.bench_function("hilbert_value_to_point", |b|
+    {
+        b.iter(||
+        {
+            (0..SIZE * SIZE).for_each(|value|
+            {
+                black_box(curve.value_to_point(black_box(value)));
+            });
+        })
+    });
+}
+
+fn hilbertify(c: &mut Criterion)
+{
+    let mut group = c.benchmark_group("remap");
+
+    // a full remap takes long enough that the default 100 samples would drag on
+    group.sample_size(20);
+
+    [(SIZE, "hilbertify_rgb"), (2048, "hilbertify_rgb_2048")].into_iter().for_each(|(size, name)|
+    {
+        group.bench_function(name, |b|
+        {
+            b.iter_batched(|| test_image(size), |mut image|
+            {
+                image.hilbertify(CurveType::Hilbert).unwrap();
+
+                image
+            }, BatchSize::LargeInput)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, curves, hilbertify);
+criterion_main!(benches);
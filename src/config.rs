@@ -2,21 +2,34 @@ use std::{
     env,
     iter,
     process,
-    fs::File,
+    fs::{self, File},
     path::{Path, PathBuf},
     fmt::{self, Display},
+    time::Duration,
     collections::HashSet,
     num::{ParseIntError, ParseFloatError}
 };
 
-use crate::complain;
+use sdl2::pixels::Color;
+
+use crate::{
+    complain,
+    Verbosity,
+    Pos2,
+    image::{PixelFormat, SampleDepth, Remap, Strictness}
+};
 
 
 enum ArgError
 {
     Parse(String),
     EnumParse{value: String, all: String},
+    SizeParse(String),
     UnexpectedArg(String),
+    UnknownOption(String),
+    ConfigRead{path: String, err: String},
+    ConfigLine{line: usize, text: String},
+    ConfigKey{key: String, all: String},
     DuplicateArg(String),
     MissingValue(String)
 }
@@ -29,7 +42,21 @@ impl Display for ArgError
         {
             Self::Parse(x) => format!("error parsing {x}"),
             Self::EnumParse{value: x, all} => format!("error parsing {x}, available options: {all}"),
+            Self::SizeParse(x) =>
+            {
+                format!("error parsing {x}, expected a number with an optional k, m or g suffix")
+            },
             Self::UnexpectedArg(x) => format!("unexpected argument {x}"),
+            Self::UnknownOption(x) => format!("unknown option: {x}"),
+            Self::ConfigRead{path, err} => format!("cant read config file {path}: {err}"),
+            Self::ConfigLine{line, text} =>
+            {
+                format!("config line {line} ({text}) must look like key=value")
+            },
+            Self::ConfigKey{key, all} =>
+            {
+                format!("unknown config key {key}, every key sets the --option of the same name: {all}")
+            },
             Self::DuplicateArg(x) => format!("duplicate argument {x}"),
             Self::MissingValue(x) => format!("missing value after {x} argument")
         })
@@ -52,10 +79,13 @@ impl From<(&str, ParseFloatError)> for ArgError
     }
 }
 
+#[derive(Clone, Copy)]
 enum ArgType
 {
     Variable,
     Flag(bool),
+    Config,
+    Meta,
     Help
 }
 
@@ -187,8 +217,27 @@ impl<'a> ArgParser<'a>
         }
     }
 
-    pub fn parse(mut self, mut args: impl Iterator<Item=String>) -> Result<(), ArgError>
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn parse(&mut self, mut args: impl Iterator<Item=String>) -> Result<(), ArgError>
     {
+        self.args.push(ArgInfo{
+            value: None,
+            short: Some('C'),
+            long: "config".to_owned(),
+            description: "file with key=value lines, options given here override it".to_owned(),
+            kind: ArgType::Config,
+            encountered: false
+        });
+
+        self.args.push(ArgInfo{
+            value: None,
+            short: None,
+            long: "from-meta".to_owned(),
+            description: "restore the size, format and remapping from a .meta file written by --meta".to_owned(),
+            kind: ArgType::Meta,
+            encountered: false
+        });
+
         self.args.push(ArgInfo{
             value: None,
             short: Some('h'),
@@ -200,6 +249,9 @@ impl<'a> ArgParser<'a>
 
         self.validate();
 
+        let mut config_path = None;
+        let mut meta_path = None;
+
         while let Some(raw_arg) = args.next()
         {
             if let Some(arg) = raw_arg.strip_prefix("--")
@@ -211,16 +263,21 @@ impl<'a> ArgParser<'a>
                         self.print_help();
                     }
 
-                    Self::on_arg(&mut args, found, &raw_arg)?;
+                    match Self::on_arg(&mut args, found, &raw_arg)?
+                    {
+                        Some((ArgType::Meta, path)) => meta_path = Some(path),
+                        Some((_, path)) => config_path = Some(path),
+                        None => ()
+                    }
                 } else
                 {
-                    return Err(ArgError::UnexpectedArg(raw_arg));
+                    return Err(ArgError::UnknownOption(raw_arg));
                 }
             } else if let Some(arg) = raw_arg.strip_prefix('-')
             {
-                if arg.len() != 1
+                if arg.chars().count() != 1
                 {
-                    return Err(ArgError::UnexpectedArg(raw_arg));
+                    return Err(ArgError::UnknownOption(raw_arg));
                 }
 
                 let c = arg.chars().next().unwrap();
@@ -232,10 +289,15 @@ impl<'a> ArgParser<'a>
                         self.print_help();
                     }
 
-                    Self::on_arg(&mut args, found, &raw_arg)?;
+                    match Self::on_arg(&mut args, found, &raw_arg)?
+                    {
+                        Some((ArgType::Meta, path)) => meta_path = Some(path),
+                        Some((_, path)) => config_path = Some(path),
+                        None => ()
+                    }
                 } else
                 {
-                    return Err(ArgError::UnexpectedArg(raw_arg));
+                    return Err(ArgError::UnknownOption(raw_arg));
                 }
             } else
             {
@@ -243,29 +305,113 @@ impl<'a> ArgParser<'a>
             }
         }
 
+        // the meta file describes the input so it wins over the general config
+        if let Some(path) = meta_path
+        {
+            self.parse_config(&path)?;
+        }
+
+        if let Some(path) = config_path
+        {
+            self.parse_config(&path)?;
+        }
+
         Ok(())
     }
 
-    fn print_help(self) -> !
+    // only fills in the args which werent given on the command line
+    fn parse_config(&mut self, path: &str) -> Result<(), ArgError>
     {
-        println!("usage: {} [args]", env::args().next().unwrap());
-
-        let longest_arg = self.args.iter().map(|arg| arg.long.len()).max()
-            .unwrap_or(0);
+        let text = fs::read_to_string(path).map_err(|err|
+        {
+            ArgError::ConfigRead{path: path.to_owned(), err: err.to_string()}
+        })?;
 
-        for arg in self.args
+        let mut seen = HashSet::new();
+        for (index, line) in text.lines().enumerate()
         {
-            println!("{}", arg.help(longest_arg));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(||
+            {
+                ArgError::ConfigLine{line: index + 1, text: line.to_owned()}
+            })?;
+
+            let (key, value) = (key.trim(), value.trim());
+
+            if !seen.insert(key.to_owned())
+            {
+                return Err(ArgError::DuplicateArg(key.to_owned()));
+            }
+
+            let all = self.config_keys();
+            let found = self.args.iter_mut()
+                .find(|arg| arg.long == key && arg.value.is_some())
+                .ok_or_else(|| ArgError::ConfigKey{key: key.to_owned(), all})?;
+
+            if found.encountered
+            {
+                continue;
+            }
+
+            found.encountered = true;
+
+            let info = match found.kind
+            {
+                ArgType::Flag(state) =>
+                {
+                    let enabled = parse_bool(value)?;
+
+                    ArgParseInfo::Flag(if enabled { state } else { !state })
+                },
+                _ => ArgParseInfo::Variable(value.to_owned())
+            };
+
+            found.value.as_mut().unwrap().parse(info)?;
         }
 
+        Ok(())
+    }
+
+    fn config_keys(&self) -> String
+    {
+        self.args.iter()
+            .filter(|arg| arg.value.is_some())
+            .map(|arg| arg.long.clone())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn print_help(&self) -> !
+    {
+        println!("{}", self.usage());
+
         process::exit(0)
     }
 
+    pub fn usage(&self) -> String
+    {
+        let program = env::args().next().unwrap_or_else(|| "imagedisplay".to_owned());
+
+        let longest_arg = self.args.iter().map(|arg| arg.long.len()).max()
+            .unwrap_or(0);
+
+        iter::once(format!("usage: {program} [args]"))
+            .chain(self.args.iter().map(|arg| arg.help(longest_arg)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // returns the path if this was a config or meta file argument
     fn on_arg(
         mut args: impl Iterator<Item=String>,
         arg: &mut ArgInfo,
         arg_value: &str
-    ) -> Result<(), ArgError>
+    ) -> Result<Option<(ArgType, String)>, ArgError>
     {
         if arg.encountered
         {
@@ -276,13 +422,19 @@ impl<'a> ArgParser<'a>
 
         let info = match arg.kind
         {
-            ArgType::Variable =>
+            ArgType::Variable | ArgType::Config | ArgType::Meta =>
             {
-                let value = args.next().ok_or_else(||
+                // another long option right after means the value was forgotten
+                let value = args.next().filter(|value| !value.starts_with("--")).ok_or_else(||
                 {
                     ArgError::MissingValue(arg_value.to_owned())
                 })?;
 
+                if let ArgType::Config | ArgType::Meta = arg.kind
+                {
+                    return Ok(Some((arg.kind, value)));
+                }
+
                 ArgParseInfo::Variable(value)
             },
             ArgType::Flag(x) => ArgParseInfo::Flag(x),
@@ -291,7 +443,7 @@ impl<'a> ArgParser<'a>
 
         arg.value.as_mut().unwrap().parse(info)?;
 
-        Ok(())
+        Ok(None)
     }
 
     fn validate(&self)
@@ -331,6 +483,54 @@ impl DisplayableDefault for PathBuf
     }
 }
 
+impl DisplayableDefault for Pos2<usize>
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(format!("{}x{}", self.x, self.y))
+    }
+}
+
+impl DisplayableDefault for ChannelOrder
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(self.to_string())
+    }
+}
+
+impl DisplayableDefault for Pipeline
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(self.to_string())
+    }
+}
+
+impl DisplayableDefault for AspectRatio
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(self.to_string())
+    }
+}
+
+impl DisplayableDefault for CropArea
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(self.to_string())
+    }
+}
+
+impl DisplayableDefault for Color
+{
+    fn display_default(&self) -> Option<String>
+    {
+        Some(format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b))
+    }
+}
+
 impl_displayable_default!{String}
 impl_displayable_default!{bool}
 impl_displayable_default!{f32}
@@ -380,6 +580,7 @@ macro_rules! iterable_enum
         $($key:ident),+
     }) =>
     {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         pub enum $enum_name
         {
             $($key,)+
@@ -461,15 +662,53 @@ macro_rules! iterable_enum
 
 iterable_enum!
 {
-    enum ProgramMode
+    enum CurveType
+    {
+        Hilbert,
+        Morton,
+        Peano,
+        Snake,
+        Grayrow
+    }
+}
+
+iterable_enum!
+{
+    enum Channel
     {
-        Train,
-        Run,
-        Test,
-        CreateDictionary,
-        TrainEmbeddings,
-        ClosestEmbeddings,
-        WeightsImage
+        R,
+        G,
+        B,
+        A
+    }
+}
+
+iterable_enum!
+{
+    enum Step
+    {
+        Crop,
+        Resize,
+        AutoSquare,
+        Unhilbertify,
+        Rotate,
+        FlipH,
+        FlipV,
+        ChannelOrder,
+        Invert,
+        Quantize,
+        Gamma,
+        ApplyMap
+    }
+}
+
+iterable_enum!
+{
+    enum Endianness
+    {
+        Le,
+        Be,
+        Native
     }
 }
 
@@ -503,11 +742,188 @@ impl ParsableInner for String
     }
 }
 
+// last WIDTHxHEIGHT in a file name like frame_640x480.raw, zero sizes dont count
+pub fn size_from_name(path: &Path) -> Option<Pos2<usize>>
+{
+    let name = path.file_name()?.to_str()?;
+
+    let digits_before = |end: usize| name[..end].bytes().rev().take_while(u8::is_ascii_digit).count();
+    let digits_after = |start: usize| name[start..].bytes().take_while(u8::is_ascii_digit).count();
+
+    name.rmatch_indices(['x', 'X']).find_map(|(index, _)|
+    {
+        let before = digits_before(index);
+        let after = digits_after(index + 1);
+
+        let x = name[index - before..index].parse().ok()?;
+        let y = name[index + 1..index + 1 + after].parse().ok()?;
+
+        (x != 0 && y != 0).then_some(Pos2{x, y})
+    })
+}
+
+// accepts 1024 based suffixes like 4k or 1M
+fn parse_size(value: &str) -> Result<usize, ArgError>
+{
+    let error = || ArgError::SizeParse(value.to_owned());
+
+    let (number, multiplier) = match value.char_indices().last()
+    {
+        Some((i, c)) if c.is_ascii_alphabetic() =>
+        {
+            let multiplier: usize = match c.to_ascii_lowercase()
+            {
+                'k' => 1 << 10,
+                'm' => 1 << 20,
+                'g' => 1 << 30,
+                _ => return Err(error())
+            };
+
+            (&value[..i], multiplier)
+        },
+        _ => (value, 1)
+    };
+
+    number.parse::<usize>().ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(error)
+}
+
+fn parse_bool(value: &str) -> Result<bool, ArgError>
+{
+    match value.to_lowercase().as_str()
+    {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(ArgError::Parse(value.to_owned()))
+    }
+}
+
 impl ParsableInner for usize
 {
     fn parse_inner(value: &str) -> Result<Self, ArgError>
     {
-        value.parse::<usize>().map_err(|err| (value, err).into())
+        parse_size(value)
+    }
+}
+
+// WIDTHxHEIGHT
+impl ParsableInner for Pos2<usize>
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let error = || ArgError::Parse(format!("{value}, expected WIDTHxHEIGHT"));
+
+        let (x, y) = value.split_once(['x', 'X']).ok_or_else(error)?;
+
+        Ok(Self{
+            x: parse_size(x).map_err(|_| error())?,
+            y: parse_size(y).map_err(|_| error())?
+        })
+    }
+}
+
+// some order of r, g and b like bgr
+impl ParsableInner for ChannelOrder
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let error = || ArgError::Parse(format!("{value}, expected some order of r, g and b"));
+
+        let channels = value.chars()
+            .map(|c| Channel::parse_inner(&c.to_string()).map_err(|_| error()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let order: [Channel; 3] = channels.try_into().map_err(|_| error())?;
+
+        let is_permutation = [Channel::R, Channel::G, Channel::B].iter().all(|channel|
+        {
+            order.contains(channel)
+        });
+
+        if !is_permutation
+        {
+            return Err(error());
+        }
+
+        Ok(Self(order))
+    }
+}
+
+// comma separated steps, each at most once
+impl ParsableInner for Pipeline
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let steps = value.split(',')
+            .map(|step| Step::parse_inner(step.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(step) = steps.iter().enumerate().find_map(|(index, step)| steps[..index].contains(step).then_some(step))
+        {
+            return Err(ArgError::Parse(format!("{value}, {step} appears more than once")));
+        }
+
+        Ok(Self(steps))
+    }
+}
+
+// W:H
+impl ParsableInner for AspectRatio
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let error = || ArgError::Parse(format!("{value}, expected W:H"));
+
+        let (x, y) = value.split_once(':').ok_or_else(error)?;
+
+        let x: usize = x.trim().parse().map_err(|_| error())?;
+        let y: usize = y.trim().parse().map_err(|_| error())?;
+
+        if x == 0 || y == 0
+        {
+            return Err(error());
+        }
+
+        Ok(Self(Pos2{x, y}))
+    }
+}
+
+// x,y,w,h
+impl ParsableInner for CropArea
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let error = || ArgError::Parse(format!("{value}, expected x,y,w,h"));
+
+        let values = value.split(',')
+            .map(|x| parse_size(x.trim()).map_err(|_| error()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match values[..]
+        {
+            [x, y, w, h] => Ok(Self{pos: Pos2{x, y}, size: Pos2{x: w, y: h}}),
+            _ => Err(error())
+        }
+    }
+}
+
+// RRGGBB with an optional #
+impl ParsableInner for Color
+{
+    fn parse_inner(value: &str) -> Result<Self, ArgError>
+    {
+        let error = || ArgError::Parse(format!("{value}, expected a RRGGBB hex color"));
+
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(error());
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+
+        Ok(Color::RGB(channel(0)?, channel(2)?, channel(4)?))
     }
 }
 
@@ -554,14 +970,128 @@ impl<T: ParsableInner + DisplayableDefault> ArgParsable for Option<T>
     }
 }
 
+// which channel ends up as red, green and blue
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelOrder(pub [Channel; 3]);
+
+impl Display for ChannelOrder
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        self.0.iter().try_for_each(|channel| write!(f, "{channel}"))
+    }
+}
+
+// order the transforms get applied in, the curve remapping when saving always comes after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline(pub Vec<Step>);
+
+impl Default for Pipeline
+{
+    fn default() -> Self
+    {
+        Self(Step::iter().collect())
+    }
+}
+
+impl Display for Pipeline
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let steps = self.0.iter().map(|step| step.to_string()).collect::<Vec<_>>();
+
+        write!(f, "{}", steps.join(","))
+    }
+}
+
+// width to height ratio like 16:9
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AspectRatio(pub Pos2<usize>);
+
+impl Display for AspectRatio
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{}:{}", self.0.x, self.0.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CropArea
+{
+    pub pos: Pos2<usize>,
+    pub size: Pos2<usize>
+}
+
+impl Display for CropArea
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{},{},{},{}", self.pos.x, self.pos.y, self.size.x, self.size.y)
+    }
+}
+
+#[derive(Clone)]
 pub struct Config
 {
-    pub width: usize,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub aspect: Option<AspectRatio>,
+    pub dims_from_name: bool,
+    pub column_major: bool,
+    pub format: PixelFormat,
+    pub background: Color,
+    pub depth: SampleDepth,
+    pub keep_depth: bool,
+    pub palette: Option<String>,
     pub input: String,
+    // read right after the input as if they were part of it
+    pub append: Vec<String>,
     pub save_path: Option<String>,
+    pub outdir: Option<String>,
+    pub force: bool,
+    pub dry_run: bool,
+    pub meta: bool,
+    pub generate: Option<Pos2<usize>>,
+    pub dump_map: Option<String>,
+    pub stats: bool,
+    pub inspect: Option<usize>,
+    pub apply_map: Option<String>,
+    pub compare: Option<String>,
+    pub pixel_offset: usize,
     pub trim_start: usize,
     pub trim_end: usize,
-    pub unhilbertify: bool
+    pub length: Option<usize>,
+    pub unhilbertify: bool,
+    pub curve: CurveType,
+    pub channel: Option<Channel>,
+    pub channel_curves: Vec<(Channel, CurveType)>,
+    pub scale: usize,
+    pub pixel_aspect: Option<AspectRatio>,
+    pub fit: bool,
+    pub canvas: Option<Pos2<usize>>,
+    pub preview_max: Option<usize>,
+    pub grid: bool,
+    pub checker: Option<usize>,
+    pub grid_color: Option<Color>,
+    pub animate: Option<Duration>,
+    pub frames: bool,
+    pub fps: usize,
+    pub verbosity: Verbosity,
+    pub strict: bool,
+    pub exact: bool,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub rotate: u8,
+    pub channel_order: Option<ChannelOrder>,
+    pub invert: bool,
+    pub quantize: Option<u8>,
+    pub gamma: Option<f32>,
+    pub gamma_save: bool,
+    pub crop: Option<CropArea>,
+    pub auto_square: bool,
+    pub resize: Option<Pos2<usize>>,
+    pub pipeline: Pipeline
 }
 
 impl Config
@@ -569,35 +1099,573 @@ impl Config
     pub fn parse(args: impl Iterator<Item=String>) -> Self
     {
         let mut input = String::new();
+        let mut append: Option<String> = None;
         let mut save_path: Option<String> = None;
-
+        let mut outdir: Option<String> = None;
+        let mut force = false;
+        let mut dry_run = false;
+        let mut meta = false;
+        let mut generate: Option<Pos2<usize>> = None;
+        let mut dump_map: Option<String> = None;
+        let mut stats = false;
+        let mut inspect: Option<usize> = None;
+        let mut apply_map: Option<String> = None;
+        let mut compare: Option<String> = None;
+
+        let mut pixel_offset = 0;
         let mut trim_start = 0;
         let mut trim_end = 0;
+        let mut length: Option<usize> = None;
 
         let mut unhilbertify = false;
+        let mut curve = CurveType::Hilbert;
+        let mut channel: Option<Channel> = None;
+
+        let mut curve_r: Option<CurveType> = None;
+        let mut curve_g: Option<CurveType> = None;
+        let mut curve_b: Option<CurveType> = None;
+        let mut curve_a: Option<CurveType> = None;
+
+        let mut scale = 1;
+        let mut pixel_aspect: Option<AspectRatio> = None;
+        let mut fit = false;
+        let mut canvas: Option<Pos2<usize>> = None;
+        let mut preview_max: Option<usize> = None;
+
+        let mut grid = false;
+
+        let mut checkerboard = false;
+        let mut checker_size = 8;
+        let mut grid_color: Option<Color> = None;
+
+        let mut animate = false;
+        let mut animate_duration: f32 = 1.5;
+
+        let mut frames = false;
+
+        let mut fps = 60;
+
+        let mut verbose = false;
+        let mut quiet = false;
+        let mut strict = false;
+        let mut exact = false;
+
+        let mut flip_horizontal = false;
+        let mut flip_vertical = false;
+
+        let mut rotate = 0;
+
+        let mut channel_order: Option<ChannelOrder> = None;
+        let mut invert = false;
+        let mut quantize: Option<usize> = None;
+
+        let mut gamma: Option<f32> = None;
+        let mut gamma_save = false;
+
+        let mut crop: Option<CropArea> = None;
+        let mut resize: Option<Pos2<usize>> = None;
+
+        let mut auto_square = false;
+
+        let mut pipeline = Pipeline::default();
 
         let mut width: Option<usize> = None;
+        let mut height: Option<usize> = None;
+        let mut aspect: Option<AspectRatio> = None;
+        let mut dims_from_name = false;
+        let mut column_major = false;
+
+        let mut bpp: Option<usize> = None;
+        let mut grayscale = false;
+
+        let mut background = Color::RGB(0, 0, 0);
+
+        let mut bit_depth = 8;
+        let mut endian = Endianness::Le;
+        let mut keep_depth = false;
+
+        let mut palette: Option<String> = None;
 
         let mut parser = ArgParser::new();
 
-        parser.push(&mut input, 'i', "input", "input file");
-        parser.push(&mut save_path, 's', "save", "save the image to this path afterwards");
-        parser.push(&mut width, 'w', "width", "width of the image");
+        parser.push(&mut input, 'i', "input", "input file (- for stdin) or a directory of files to save into --save or --outdir");
+        parser.push(&mut append, None, "append", "comma separated files read after the input as if they were one file");
+        parser.push(&mut save_path, 's', "save", "save the image to this path afterwards (- for raw bytes to stdout)");
+        parser.push(&mut outdir, None, "outdir", "save into this directory named after the input and transforms (like foo.invert.hilbert.raw)");
+        parser.push_flag(&mut force, None, "force", "allow saving over the input file", true);
+        parser.push_flag(&mut dry_run, None, "dry-run", "print the size, padding and transforms of the input and exit without saving", true);
+        parser.push_flag(&mut meta, None, "meta", "also write the size, format and transforms of the saved image into a .meta file next to it", true);
+        parser.push(&mut generate, None, "generate", "make a WIDTHxHEIGHT gradient along the curve instead of reading input");
+        parser.push(&mut dump_map, None, "dump-map", "write the destination index of every pixel as little endian u32s (u64s if needed)");
+        parser.push_flag(&mut stats, None, "stats", "print per channel statistics of the input and exit", true);
+        parser.push(&mut inspect, None, "inspect", "print a hex dump of the first N bytes of the input and exit");
+        parser.push(&mut apply_map, None, "apply-map", "reorder the pixels with a --dump-map file, both in the window and in place of the curve when saving");
+        parser.push(&mut compare, None, "compare", "second input file shown next to the first one in the window");
+        parser.push(&mut width, 'w', "width", "width of the image (inferred as a square if missing)");
+        parser.push(&mut height, 'H', "height", "height of the image (inferred from the width if missing)");
+        parser.push(&mut aspect, None, "aspect", "W:H aspect ratio to infer the size from instead of a square");
+        parser.push_flag(&mut dims_from_name, None, "dims-from-name", "take the size from a WIDTHxHEIGHT in the input file name (like frame_640x480.raw) if --width isnt given", true);
+        parser.push_flag(&mut column_major, None, "column-major", "the input goes top to bottom before left to right, raw saves keep that order", true);
+        parser.push(&mut bpp, 'b', "bpp", "bytes per 8 bit pixel (1 for grayscale, 3 for rgb, 4 for rgba) (default 3)");
+        parser.push_flag(&mut grayscale, 'g', "grayscale", "treat every byte as a grayscale pixel", true);
+        parser.push(&mut background, 'B', "background", "RRGGBB hex color used for padding and the window");
+        parser.push(&mut bit_depth, 'd', "bit-depth", "bits per color channel (8 or 16)");
+        parser.push(&mut endian, 'e', "endian", "byte order of 16 bit channels (native is this machines order, 8 bit ignores it)");
+        parser.push_flag(&mut keep_depth, None, "keep-depth", "save raw output with the input bit depth", true);
+        parser.push(&mut palette, None, "palette", "768 byte rgb palette file, every input byte becomes the color at that index");
+        parser.push(&mut pixel_offset, None, "pixel-offset", "byte where the pixel data starts, the trims apply after it");
         parser.push(&mut trim_start, 't', "trim-start", "trims this amount of bytes from the start");
         parser.push(&mut trim_end, 'T', "trim-end", "trims this amount of bytes from the end");
+        parser.push(&mut length, None, "length", "read exactly this many bytes after --trim-start, ignores --trim-end");
         parser.push_flag(&mut unhilbertify, 'u', "unhilbertify", "unhilbertify the image", true);
+        parser.push(&mut curve, 'c', "curve", "space filling curve used for the remapping");
+        parser.push(&mut channel, None, "channel", "only remap this color channel (r, g, b or a)");
+        parser.push(&mut curve_r, None, "curve-r", "remap the red channel with its own curve, unlisted channels stay raster");
+        parser.push(&mut curve_g, None, "curve-g", "remap the green channel with its own curve");
+        parser.push(&mut curve_b, None, "curve-b", "remap the blue channel with its own curve");
+        parser.push(&mut curve_a, None, "curve-a", "remap the alpha channel with its own curve");
+        parser.push(&mut scale, 'S', "scale", "size of each pixel in the window");
+        parser.push(&mut pixel_aspect, None, "pixel-aspect", "W:H shape of each pixel in the window, 2:1 draws pixels twice as wide as tall");
+        parser.push_flag(&mut fit, 'f', "fit", "shrink the window to fit the display", true);
+        parser.push(&mut canvas, None, "canvas", "WIDTHxHEIGHT window to show the image centered in");
+        parser.push(&mut preview_max, None, "preview-max", "show a nearest neighbor copy with the longest side at most N, saving still uses the full image");
+        parser.push_flag(&mut grid, None, "grid", "draw lines between the pixels in the window (toggled with g)", true);
+        parser.push_flag(&mut checkerboard, None, "checkerboard", "show transparent pixels over a gray checkerboard instead of the background", true);
+        parser.push(&mut checker_size, None, "checker-size", "size of the --checkerboard squares in window pixels");
+        parser.push(&mut grid_color, None, "grid-color", "RRGGBB hex color of the --grid lines (contrasts the background by default)");
+        parser.push_flag(&mut animate, None, "animate", "animate the pixels moving when toggling the curve with h", true);
+        parser.push(&mut animate_duration, None, "animate-duration", "length of the --animate animation in seconds");
+        parser.push_flag(&mut frames, None, "frames", "play the input as back to back frames of --width by --height pixels, arrows step and space pauses", true);
+        parser.push(&mut fps, None, "fps", "how many times per second --animate redraws the window or --frames advances");
+        parser.push_flag(&mut verbose, 'v', "verbose", "print extra diagnostics", true);
+        parser.push_flag(&mut quiet, 'q', "quiet", "only print errors", true);
+        parser.push_flag(&mut strict, None, "strict", "fail instead of warning about trailing bytes, extra padding or ignored options", true);
+        parser.push_flag(&mut exact, None, "exact", "fail unless the input is exactly as big as the image, without padding or leftovers", true);
+        parser.push_flag(&mut flip_horizontal, None, "flip-h", "flip the image horizontally", true);
+        parser.push_flag(&mut flip_vertical, None, "flip-v", "flip the image vertically", true);
+        parser.push(&mut rotate, 'r', "rotate", "rotate the image clockwise by 90, 180 or 270 degrees");
+        parser.push(&mut channel_order, None, "channel-order", "reorder the color channels, bgr swaps red and blue");
+        parser.push_flag(&mut invert, None, "invert", "invert the colors", true);
+        parser.push(&mut quantize, None, "quantize", "snap every channel to this many levels (2 to 255)");
+        parser.push(&mut gamma, None, "gamma", "gamma correction for the window");
+        parser.push_flag(&mut gamma_save, None, "gamma-save", "apply --gamma to the saved output too", true);
+        parser.push(&mut crop, None, "crop", "crop the image to the x,y,w,h rectangle");
+        parser.push(&mut resize, None, "resize", "resize the image to WIDTHxHEIGHT (nearest neighbor)");
+        parser.push_flag(&mut auto_square, None, "auto-square", "crop the biggest centered power of 2 square", true);
+        parser.push(&mut pipeline, None, "pipeline", "comma separated order of the transforms, saving remaps with the curve last");
+
+        if let Err(err) = parser.parse(args)
+        {
+            complain(format!("{err}\n{}", parser.usage()));
+        }
+
+        let verbosity = match (verbose, quiet)
+        {
+            (true, true) => complain("cant be both verbose and quiet"),
+            (true, false) => Verbosity::Verbose,
+            (false, true) => Verbosity::Quiet,
+            (false, false) => Verbosity::Normal
+        };
+
+        if scale == 0
+        {
+            complain("scale must be at least 1");
+        }
+
+        if resize.is_some_and(|size| size.x == 0 || size.y == 0)
+        {
+            complain("cant resize to an empty image");
+        }
+
+        let quantize = quantize.map(|levels|
+        {
+            u8::try_from(levels).ok().filter(|levels| *levels >= 2).unwrap_or_else(||
+            {
+                complain(format!("quantize levels must be between 2 and 255, got {levels}"))
+            })
+        });
+
+        if gamma.is_some_and(|gamma| !(gamma > 0.0 && gamma.is_finite()))
+        {
+            complain("gamma must be a positive number");
+        }
+
+        if save_path.is_some() && outdir.is_some()
+        {
+            complain("cant use both --save and --outdir");
+        }
+
+        if outdir.is_some() && generate.is_some()
+        {
+            complain("--outdir names files after the input, use --save with --generate");
+        }
+
+        if meta && (save_path.is_none() && outdir.is_none())
+        {
+            complain("--meta describes a saved image, use it with --save or --outdir");
+        }
+
+        if meta && save_path.as_deref() == Some("-")
+        {
+            complain("--meta needs a file to write next to, not stdout");
+        }
+
+        let append = append.map(|append|
+        {
+            append.split(',').map(|path| path.trim().to_owned()).collect::<Vec<_>>()
+        }).unwrap_or_default();
+
+        if !append.is_empty()
+        {
+            if append.iter().any(|path| path.is_empty())
+            {
+                complain("--append has an empty path");
+            }
+
+            if input == "-" || append.iter().any(|path| path == "-")
+            {
+                complain("stdin cant be concatenated with other files");
+            }
+
+            if generate.is_some() || compare.is_some()
+            {
+                complain("--append only works with a single input file");
+            }
+        }
+
+        if dry_run && generate.is_some()
+        {
+            complain("--dry-run needs an input to check");
+        }
+
+        if compare.is_some() && (save_path.is_some() || outdir.is_some() || generate.is_some())
+        {
+            complain("--compare only works when showing an input file in the window");
+        }
+
+        if aspect.is_some() && (width.is_some() || height.is_some())
+        {
+            complain("--aspect cant be used together with --width or --height");
+        }
+
+        if inspect == Some(0)
+        {
+            complain("inspect needs at least 1 byte");
+        }
+
+        if length == Some(0)
+        {
+            complain("length must be at least 1 byte");
+        }
+
+        if length.is_some() && trim_end != 0
+        {
+            if strict
+            {
+                complain("--trim-end cant be combined with --length when strict");
+            }
+
+            verbosity.info("ignoring --trim-end because --length is set");
+        }
+
+        if canvas.is_some_and(|size| size.x == 0 || size.y == 0)
+        {
+            complain("canvas must be at least 1x1");
+        }
+
+        if checker_size == 0
+        {
+            complain("checker size must be at least 1 pixel");
+        }
+
+        let checker = checkerboard.then_some(checker_size);
+
+        if preview_max == Some(0)
+        {
+            complain("preview max must be at least 1 pixel");
+        }
+
+        if !(animate_duration > 0.0 && animate_duration.is_finite())
+        {
+            complain("animate duration must be a positive number");
+        }
+
+        if !(1..=1000).contains(&fps)
+        {
+            complain(format!("fps must be between 1 and 1000, got {fps}"));
+        }
+
+        let animate = animate.then(|| Duration::from_secs_f32(animate_duration));
+
+        if frames
+        {
+            if save_path.is_some() || outdir.is_some() || compare.is_some() || generate.is_some()
+            {
+                complain("--frames only plays the input in the window");
+            }
+
+            if dry_run || stats || inspect.is_some()
+            {
+                complain("--frames cant be combined with --dry-run, --stats or --inspect");
+            }
+
+            if input == "-"
+            {
+                complain("--frames needs a file to seek in, not stdin");
+            }
+        }
+
+        if generate.is_some_and(|size| size.x == 0 || size.y == 0)
+        {
+            complain("cant generate an empty image");
+        }
+
+        let rotate = match rotate
+        {
+            0 | 90 | 180 | 270 => (rotate / 90) as u8,
+            x => complain(format!("can only rotate by 90, 180 or 270 degrees, got {x}"))
+        };
+
+        let depth = match bit_depth
+        {
+            8 => SampleDepth::Eight,
+            16 => SampleDepth::Sixteen(endian),
+            x => complain(format!("bit depth must be 8 or 16, got {x}"))
+        };
+
+        if palette.is_some()
+        {
+            if grayscale || bpp.is_some()
+            {
+                complain("palette input always has 1 index byte per pixel, --bpp and --grayscale dont apply");
+            }
+
+            if bit_depth != 8
+            {
+                complain("palette indices are always 8 bit");
+            }
+        }
+
+        let format = match (grayscale, bpp)
+        {
+            _ if palette.is_some() => PixelFormat::Rgb,
+            (true, Some(bpp)) if bpp != 1 =>
+            {
+                complain(format!("grayscale images have 1 byte per pixel, got bpp {bpp}"))
+            },
+            (true, _) => PixelFormat::Grayscale,
+            (false, bpp) =>
+            {
+                let bpp = bpp.unwrap_or(3);
+
+                PixelFormat::from_bpp(bpp).unwrap_or_else(||
+                {
+                    complain(format!("unsupported bpp {bpp}, must be 1, 3 or 4"))
+                })
+            }
+        };
+
+        let channel_curves = [(Channel::R, curve_r), (Channel::G, curve_g), (Channel::B, curve_b), (Channel::A, curve_a)]
+            .into_iter()
+            .filter_map(|(channel, curve)| curve.map(|curve| (channel, curve)))
+            .collect::<Vec<_>>();
+
+        if !channel_curves.is_empty()
+        {
+            if channel.is_some()
+            {
+                complain("--channel cant be combined with per channel curves");
+            }
+
+            if apply_map.is_some()
+            {
+                complain("--apply-map cant be combined with per channel curves");
+            }
+
+            if format == PixelFormat::Grayscale
+            {
+                complain("grayscale images only have one channel, use --curve");
+            }
 
-        parser.parse(args).unwrap_or_else(|err| complain(err));
+            if curve_a.is_some() && !format.has_alpha()
+            {
+                complain("cant remap the alpha channel of an image without alpha");
+            }
+        }
 
-        let width = width.unwrap_or_else(|| complain("must provide a width argument"));
+        match (channel, format)
+        {
+            (Some(Channel::A), PixelFormat::Grayscale | PixelFormat::Rgb) =>
+            {
+                complain("cant remap the alpha channel of an image without alpha")
+            },
+            (Some(x), PixelFormat::Grayscale) =>
+            {
+                complain(format!("cant remap only the {x} channel of a grayscale image"))
+            },
+            _ => ()
+        }
 
-        Self{
+        let this = Self{
             width,
+            height,
+            aspect,
+            dims_from_name,
+            column_major,
+            format,
+            background,
+            depth,
+            keep_depth,
+            palette,
             input,
+            append,
             save_path,
+            outdir,
+            force,
+            dry_run,
+            meta,
+            generate,
+            dump_map,
+            stats,
+            inspect,
+            apply_map,
+            compare,
+            pixel_offset,
             trim_start,
             trim_end,
-            unhilbertify
+            length,
+            unhilbertify,
+            curve,
+            channel,
+            channel_curves,
+            scale,
+            pixel_aspect,
+            fit,
+            canvas,
+            preview_max,
+            grid,
+            checker,
+            grid_color,
+            animate,
+            frames,
+            fps,
+            verbosity,
+            strict,
+            exact,
+            flip_horizontal,
+            flip_vertical,
+            rotate,
+            channel_order,
+            invert,
+            quantize,
+            gamma,
+            gamma_save,
+            crop,
+            auto_square,
+            resize,
+            pipeline
+        };
+
+        if let Some(step) = Step::iter().find(|step| this.step_enabled(*step) && !this.pipeline.0.contains(step))
+        {
+            complain(format!("{step} is enabled but missing from --pipeline"));
+        }
+
+        this
+    }
+
+    pub fn saving(&self) -> bool
+    {
+        self.save_path.is_some() || self.outdir.is_some()
+    }
+
+    // where the image made from this input gets saved, if anywhere
+    pub fn output_path(&self, input: &Path) -> Option<PathBuf>
+    {
+        match (&self.save_path, &self.outdir)
+        {
+            (Some(path), _) => Some(PathBuf::from(path)),
+            (None, Some(outdir)) => Some(Path::new(outdir).join(self.auto_name(input))),
+            (None, None) => None
+        }
+    }
+
+    // input name with the applied transforms and the curve, like foo.invert.hilbert.raw
+    pub fn auto_name(&self, input: &Path) -> String
+    {
+        let stem = input.file_stem()
+            .filter(|_| input != Path::new("-"))
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "stdin".to_owned());
+
+        let steps = self.enabled_steps().into_iter().map(|step| step.to_string());
+
+        let remap = match self.save_remap()
+        {
+            Remap::None => None,
+            remap => Some(remap.to_string())
+        };
+
+        let tags = steps.chain(remap).collect::<Vec<_>>();
+
+        format!("{stem}.{}.raw", tags.join("."))
+    }
+
+    // exact checks everything strict does and more
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn strictness(&self) -> Strictness
+    {
+        if self.exact
+        {
+            Strictness::Exact
+        } else if self.strict
+        {
+            Strictness::Strict
+        } else
+        {
+            Strictness::Lenient
+        }
+    }
+
+    // transforms that actually run, in pipeline order
+    pub fn enabled_steps(&self) -> Vec<Step>
+    {
+        self.pipeline.0.iter().copied().filter(|step| self.step_enabled(*step)).collect()
+    }
+
+    // whether the flags ask for this transform
+    pub fn step_enabled(&self, step: Step) -> bool
+    {
+        match step
+        {
+            Step::Crop => self.crop.is_some(),
+            Step::Resize => self.resize.is_some(),
+            Step::AutoSquare => self.auto_square,
+            Step::Unhilbertify => self.unhilbertify,
+            Step::Rotate => self.rotate != 0,
+            Step::FlipH => self.flip_horizontal,
+            Step::FlipV => self.flip_vertical,
+            Step::ChannelOrder => self.channel_order.is_some(),
+            Step::Invert => self.invert,
+            Step::Quantize => self.quantize.is_some(),
+            Step::Gamma => self.gamma.is_some(),
+            Step::ApplyMap => self.apply_map.is_some()
+        }
+    }
+
+    // how saving reorders the pixels, an applied map already did the reordering
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn save_remap(&self) -> Remap
+    {
+        if self.apply_map.is_some()
+        {
+            Remap::None
+        } else if !self.channel_curves.is_empty()
+        {
+            Remap::Channels(self.channel_curves.clone())
+        } else
+        {
+            Remap::Curve(self.curve)
         }
     }
 
@@ -613,3 +1681,133 @@ impl Config
             })
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn size_suffixes()
+    {
+        assert_eq!(parse_size("123").ok(), Some(123));
+        assert_eq!(parse_size("1k").ok(), Some(1024));
+        assert_eq!(parse_size("4K").ok(), Some(4096));
+        assert_eq!(parse_size("1M").ok(), Some(1024 * 1024));
+        assert_eq!(parse_size("2g").ok(), Some(2 * 1024 * 1024 * 1024));
+
+        ["", "k", "1kk", "1x", "-1", "1.5k"].into_iter().for_each(|x|
+        {
+            assert!(parse_size(x).is_err(), "{x} shouldnt parse");
+        });
+    }
+
+    #[test]
+    fn channel_orders()
+    {
+        let order = ChannelOrder::parse_inner("bgr").ok().map(|x| x.0);
+        assert_eq!(order, Some([Channel::B, Channel::G, Channel::R]));
+
+        assert_eq!(ChannelOrder([Channel::G, Channel::R, Channel::B]).to_string(), "grb");
+
+        assert!(ChannelOrder::parse_inner("GBR").is_ok());
+
+        ["", "rg", "rgbr", "rrb", "rga", "xyz"].into_iter().for_each(|x|
+        {
+            assert!(ChannelOrder::parse_inner(x).is_err(), "{x} shouldnt parse");
+        });
+    }
+
+    #[test]
+    fn pipelines()
+    {
+        assert_eq!(
+            Pipeline::parse_inner("invert, crop,flip_h").ok(),
+            Some(Pipeline(vec![Step::Invert, Step::Crop, Step::FlipH]))
+        );
+
+        assert_eq!(Pipeline::parse_inner(&Pipeline::default().to_string()).ok(), Some(Pipeline::default()));
+
+        ["", "crop,", "crop,crop", "blur"].into_iter().for_each(|x|
+        {
+            assert!(Pipeline::parse_inner(x).is_err(), "{x} shouldnt parse");
+        });
+    }
+
+    #[test]
+    fn auto_names()
+    {
+        let parse = |args: &[&str]| Config::parse(args.iter().map(|x| x.to_string()));
+
+        let config = parse(&["--outdir", "out", "--invert", "--crop", "0,0,2,2", "-c", "morton"]);
+
+        assert_eq!(config.auto_name(Path::new("dumps/foo.bin")), "foo.crop.invert.morton.raw");
+        assert_eq!(config.output_path(Path::new("foo")), Some(Path::new("out").join("foo.crop.invert.morton.raw")));
+
+        let config = parse(&["--apply-map", "map.bin", "--pipeline", "apply_map,flip_v", "--flip-v"]);
+
+        assert_eq!(config.auto_name(Path::new("-")), "stdin.apply_map.flip_v.raw");
+        assert_eq!(config.output_path(Path::new("foo")), None);
+    }
+
+    #[test]
+    fn from_meta()
+    {
+        use crate::image::{Image, meta_path};
+
+        let path = meta_path(env::temp_dir().join("imagedisplay_from_meta.raw"));
+
+        let mut image = Image::new(6, 4, PixelFormat::Rgba, Color::RGB(16, 32, 255));
+        image.save_depth = SampleDepth::Sixteen(Endianness::Be);
+
+        let meta = image.meta(&Remap::Curve(CurveType::Morton), &[Step::Crop, Step::Invert]);
+        assert!(meta.starts_with("# transforms: crop -> invert\n"));
+
+        fs::write(&path, meta).unwrap();
+
+        let parse = |args: &[&str]| Config::parse(args.iter().map(|x| x.to_string()));
+
+        let path = path.to_str().unwrap();
+        let config = parse(&["--from-meta", path]);
+
+        assert_eq!((config.width, config.height), (Some(6), Some(4)));
+        assert_eq!(config.format, PixelFormat::Rgba);
+        assert_eq!(config.background, Color::RGB(16, 32, 255));
+        assert_eq!(config.depth, SampleDepth::Sixteen(Endianness::Be));
+        assert!(config.unhilbertify);
+        assert_eq!(config.curve, CurveType::Morton);
+
+        // the command line still wins
+        let config = parse(&["--from-meta", path, "-c", "snake"]);
+
+        assert_eq!(config.curve, CurveType::Snake);
+    }
+
+    #[test]
+    fn sizes_from_names()
+    {
+        let size = |name: &str| size_from_name(Path::new(name)).map(|size| (size.x, size.y));
+
+        assert_eq!(size("frame_640x480.raw"), Some((640, 480)));
+        assert_eq!(size("dumps/320X200"), Some((320, 200)));
+        assert_eq!(size("a_2x3_then_16x9.bin"), Some((16, 9)));
+        assert_eq!(size("0x10_dump_8x8.bin"), Some((8, 8)));
+
+        ["frame.raw", "x480.raw", "640x.raw", "0x10.bin", "-", "640x480/frame.raw"].into_iter().for_each(|name|
+        {
+            assert_eq!(size(name), None, "{name} shouldnt have a size");
+        });
+    }
+
+    #[test]
+    fn hex_colors()
+    {
+        assert_eq!(Color::parse_inner("ff8000").ok(), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(Color::parse_inner("#0A0b0c").ok(), Some(Color::RGB(10, 11, 12)));
+
+        ["", "fff", "ff80000", "gg0000", "#", "+f8000"].into_iter().for_each(|x|
+        {
+            assert!(Color::parse_inner(x).is_err(), "{x} shouldnt parse");
+        });
+    }
+}
@@ -0,0 +1,504 @@
+use crate::{
+    Pos2,
+    image::ImageError,
+    config::CurveType
+};
+
+
+pub trait SpaceFillingCurve: Sync
+{
+    fn point_to_value(&self, pos: Pos2<usize>) -> usize;
+    fn value_to_point(&self, value: usize) -> Pos2<usize>;
+}
+
+fn power_of_two_order(size: usize) -> Result<usize, ImageError>
+{
+    if !size.is_power_of_two()
+    {
+        return Err(ImageError::NotPowerOfTwo(size));
+    }
+
+    let mut order = 0;
+
+    let mut current = size;
+    while current > 0
+    {
+        current /= 2;
+
+        order += 1;
+    }
+
+    order -= 1;
+
+    Ok(order)
+}
+
+fn power_of_three_order(size: usize) -> Result<usize, ImageError>
+{
+    let mut order = 0;
+
+    let mut current = size;
+    while current > 1 && current.is_multiple_of(3)
+    {
+        current /= 3;
+
+        order += 1;
+    }
+
+    if current != 1
+    {
+        return Err(ImageError::NotPowerOfThree(size));
+    }
+
+    Ok(order)
+}
+
+pub fn next_power_of(base: usize, value: usize) -> usize
+{
+    let mut power = 1;
+    while power < value
+    {
+        power *= base;
+    }
+
+    power
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HilbertCurve
+{
+    order: usize
+}
+
+impl HilbertCurve
+{
+    pub fn new(size: usize) -> Result<Self, ImageError>
+    {
+        Ok(Self{order: power_of_two_order(size)?})
+    }
+
+    pub fn order(&self) -> usize
+    {
+        self.order
+    }
+
+    // side length of the square the curve fills
+    pub fn size(&self) -> usize
+    {
+        2_usize.pow(self.order as u32)
+    }
+
+    // every point of the square in curve order, computed lazily
+    pub fn points(&self) -> impl Iterator<Item=Pos2<usize>>
+    {
+        let curve = *self;
+        let size = self.size();
+
+        (0..size * size).map(move |value| curve.value_to_point(value))
+    }
+
+    fn rotate(&self, mut pos: Pos2<usize>, check: Pos2<usize>, value: usize) -> Pos2<usize>
+    {
+        if check.y != 0
+        {
+            return pos;
+        }
+
+        if check.x == 1
+        {
+            pos = Pos2{x: value - 1, y: value - 1} - pos;
+        }
+
+        Pos2{x: pos.y, y: pos.x}
+    }
+}
+
+impl SpaceFillingCurve for HilbertCurve
+{
+    fn point_to_value(&self, mut pos: Pos2<usize>) -> usize
+    {
+        let n = self.size();
+
+        (0..self.order).rev().map(|s|
+        {
+            let s = 2_usize.pow(s as u32);
+
+            let rx = ((pos.x & s) > 0) as usize;
+            let ry = ((pos.y & s) > 0) as usize;
+
+            // same as xy2d on wikipedia, flipping with the full size only touches the
+            // bits above s which are never looked at again, so its the same as using s here
+            pos = self.rotate(pos, Pos2{x: rx, y: ry}, n);
+
+            s * s * ((3 * rx) ^ ry)
+        }).sum()
+    }
+
+    fn value_to_point(&self, mut value: usize) -> Pos2<usize>
+    {
+        let mut pos = Pos2{x: 0, y: 0};
+
+        for s in 0..self.order
+        {
+            let s = 2_usize.pow(s as u32);
+
+            let rx = (value / 2) & 1;
+            let ry = (value ^ rx) & 1;
+
+            pos = self.rotate(pos, Pos2{x: rx, y: ry}, s);
+
+            pos = pos + Pos2{x: rx, y: ry} * s;
+
+            value /= 4;
+        }
+
+        pos
+    }
+}
+
+pub struct MortonCurve
+{
+    order: usize
+}
+
+impl MortonCurve
+{
+    pub fn new(size: usize) -> Result<Self, ImageError>
+    {
+        Ok(Self{order: power_of_two_order(size)?})
+    }
+}
+
+impl SpaceFillingCurve for MortonCurve
+{
+    fn point_to_value(&self, pos: Pos2<usize>) -> usize
+    {
+        (0..self.order).map(|i|
+        {
+            let x = (pos.x >> i) & 1;
+            let y = (pos.y >> i) & 1;
+
+            (x << (2 * i)) | (y << (2 * i + 1))
+        }).sum()
+    }
+
+    fn value_to_point(&self, value: usize) -> Pos2<usize>
+    {
+        (0..self.order).fold(Pos2{x: 0, y: 0}, |mut pos, i|
+        {
+            pos.x |= ((value >> (2 * i)) & 1) << i;
+            pos.y |= ((value >> (2 * i + 1)) & 1) << i;
+
+            pos
+        })
+    }
+}
+
+// peanos original digit definition, each base 3 digit pair picks a 3x3 cell
+// and a digit gets mirrored when the digits of the other axis before it sum to an odd number
+pub struct PeanoCurve
+{
+    order: usize
+}
+
+impl PeanoCurve
+{
+    pub fn new(size: usize) -> Result<Self, ImageError>
+    {
+        Ok(Self{order: power_of_three_order(size)?})
+    }
+
+    fn mirror(digit: usize, sum: usize) -> usize
+    {
+        if sum % 2 == 1 { 2 - digit } else { digit }
+    }
+}
+
+impl SpaceFillingCurve for PeanoCurve
+{
+    fn point_to_value(&self, pos: Pos2<usize>) -> usize
+    {
+        let mut x_sum = 0;
+        let mut y_sum = 0;
+
+        (0..self.order).rev().fold(0, |value, i|
+        {
+            let shift = 3_usize.pow(i as u32);
+
+            let x = Self::mirror((pos.x / shift) % 3, y_sum);
+            x_sum += x;
+
+            let y = Self::mirror((pos.y / shift) % 3, x_sum);
+            y_sum += y;
+
+            value * 9 + x * 3 + y
+        })
+    }
+
+    fn value_to_point(&self, value: usize) -> Pos2<usize>
+    {
+        let mut x_sum = 0;
+        let mut y_sum = 0;
+
+        (0..self.order).rev().fold(Pos2{x: 0, y: 0}, |mut pos, i|
+        {
+            let shift = 3_usize.pow(i as u32);
+            let digits = (value / (shift * shift)) % 9;
+
+            let x = digits / 3;
+            let y = digits % 3;
+
+            pos.x += Self::mirror(x, y_sum) * shift;
+            x_sum += x;
+
+            pos.y += Self::mirror(y, x_sum) * shift;
+            y_sum += y;
+
+            pos
+        })
+    }
+}
+
+// even rows go left to right, odd rows go right to left
+pub struct SnakeCurve
+{
+    width: usize
+}
+
+impl SnakeCurve
+{
+    pub fn new(width: usize) -> Self
+    {
+        Self{width}
+    }
+}
+
+impl SpaceFillingCurve for SnakeCurve
+{
+    fn point_to_value(&self, pos: Pos2<usize>) -> usize
+    {
+        let x = if pos.y.is_multiple_of(2) { pos.x } else { self.width - 1 - pos.x };
+
+        pos.y * self.width + x
+    }
+
+    fn value_to_point(&self, value: usize) -> Pos2<usize>
+    {
+        let y = value / self.width;
+        let x = value % self.width;
+
+        let x = if y.is_multiple_of(2) { x } else { self.width - 1 - x };
+
+        Pos2{x, y}
+    }
+}
+
+// rows get reordered so the nth row is the one at the gray code of n, columns stay the same
+// gray codes past the height get skipped so any height works
+pub struct GrayRowCurve
+{
+    width: usize,
+    // row index for every position along the curve
+    rows: Vec<usize>,
+    // position along the curve for every row index
+    ranks: Vec<usize>
+}
+
+impl GrayRowCurve
+{
+    pub fn new(width: usize, height: usize) -> Self
+    {
+        let rows = (0..height.next_power_of_two())
+            .map(|i| i ^ (i >> 1))
+            .filter(|row| *row < height)
+            .collect::<Vec<_>>();
+
+        let mut ranks = vec![0; height];
+        rows.iter().enumerate().for_each(|(rank, row)| ranks[*row] = rank);
+
+        Self{width, rows, ranks}
+    }
+}
+
+impl SpaceFillingCurve for GrayRowCurve
+{
+    fn point_to_value(&self, pos: Pos2<usize>) -> usize
+    {
+        self.ranks[pos.y] * self.width + pos.x
+    }
+
+    fn value_to_point(&self, value: usize) -> Pos2<usize>
+    {
+        Pos2{x: value % self.width, y: self.rows[value / self.width]}
+    }
+}
+
+impl CurveType
+{
+    pub fn curve(&self, width: usize, height: usize) -> Result<Box<dyn SpaceFillingCurve>, ImageError>
+    {
+        Ok(match self
+        {
+            Self::Hilbert => Box::new(HilbertCurve::new(width)?),
+            Self::Morton => Box::new(MortonCurve::new(width)?),
+            Self::Peano => Box::new(PeanoCurve::new(width)?),
+            Self::Snake => Box::new(SnakeCurve::new(width)),
+            Self::Grayrow => Box::new(GrayRowCurve::new(width, height))
+        })
+    }
+
+    // square side lengths have to be a power of this
+    pub fn square_base(&self) -> Option<usize>
+    {
+        match self
+        {
+            Self::Hilbert | Self::Morton => Some(2),
+            Self::Peano => Some(3),
+            Self::Snake | Self::Grayrow => None
+        }
+    }
+
+    pub fn needs_square(&self) -> bool
+    {
+        self.square_base().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn inverse_hilbert()
+    {
+        let n = 512;
+
+        let curve: &dyn SpaceFillingCurve = &HilbertCurve::new(n).unwrap();
+
+        let total = n * n;
+        for i in 0..total
+        {
+            let point = curve.value_to_point(i);
+
+            assert_eq!(curve.point_to_value(point), i);
+        }
+    }
+
+    #[test]
+    fn hilbert_not_power_of_two()
+    {
+        assert!(matches!(HilbertCurve::new(0), Err(ImageError::NotPowerOfTwo(0))));
+        assert!(matches!(HilbertCurve::new(12), Err(ImageError::NotPowerOfTwo(12))));
+        assert!(HilbertCurve::new(1).is_ok());
+    }
+
+    #[test]
+    fn hilbert_order_size()
+    {
+        [(1, 0), (2, 1), (4, 2), (512, 9)].into_iter().for_each(|(size, order)|
+        {
+            let curve = HilbertCurve::new(size).unwrap();
+
+            assert_eq!(curve.order(), order);
+            assert_eq!(curve.size(), size);
+        });
+    }
+
+    #[test]
+    fn hilbert_points()
+    {
+        let curve = HilbertCurve::new(16).unwrap();
+
+        assert_eq!(curve.points().count(), 16 * 16);
+        assert_eq!(curve.points().next(), Some(Pos2{x: 0, y: 0}));
+
+        curve.points().enumerate().for_each(|(value, point)|
+        {
+            assert_eq!(curve.point_to_value(point), value);
+        });
+    }
+
+    #[test]
+    fn hilbert_small_sizes()
+    {
+        [2, 4, 8, 16].into_iter().for_each(|n|
+        {
+            let curve: &dyn SpaceFillingCurve = &HilbertCurve::new(n).unwrap();
+
+            let points = (0..n * n).map(|i| curve.value_to_point(i)).collect::<Vec<_>>();
+
+            points.iter().enumerate().for_each(|(i, point)|
+            {
+                assert_eq!(curve.point_to_value(*point), i);
+            });
+
+            // every step along a hilbert curve moves to a neighboring pixel
+            points.windows(2).for_each(|pair|
+            {
+                let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+
+                assert_eq!(distance, 1, "size {n}, {:?} -> {:?}", pair[0], pair[1]);
+            });
+        });
+    }
+
+    #[test]
+    fn inverse_morton()
+    {
+        let n = 512;
+
+        let curve: &dyn SpaceFillingCurve = &MortonCurve::new(n).unwrap();
+
+        let total = n * n;
+        for i in 0..total
+        {
+            let point = curve.value_to_point(i);
+
+            assert_eq!(curve.point_to_value(point), i);
+        }
+    }
+
+    #[test]
+    fn peano_27()
+    {
+        let n = 27;
+
+        let curve: &dyn SpaceFillingCurve = &PeanoCurve::new(n).unwrap();
+
+        let points = (0..n * n).map(|i| curve.value_to_point(i)).collect::<Vec<_>>();
+
+        points.iter().enumerate().for_each(|(i, point)|
+        {
+            assert!(point.x < n && point.y < n);
+            assert_eq!(curve.point_to_value(*point), i);
+        });
+
+        points.windows(2).for_each(|pair|
+        {
+            let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+
+            assert_eq!(distance, 1, "{:?} -> {:?}", pair[0], pair[1]);
+        });
+
+        assert!(matches!(PeanoCurve::new(12), Err(ImageError::NotPowerOfThree(12))));
+        assert!(matches!(PeanoCurve::new(0), Err(ImageError::NotPowerOfThree(0))));
+    }
+
+    #[test]
+    fn inverse_snake()
+    {
+        let width = 37;
+        let height = 23;
+
+        let curve: &dyn SpaceFillingCurve = &SnakeCurve::new(width);
+
+        let total = width * height;
+        for i in 0..total
+        {
+            let point = curve.value_to_point(i);
+
+            assert_eq!(curve.point_to_value(point), i);
+        }
+    }
+}
@@ -0,0 +1,2791 @@
+use std::{
+    fs::{self, File},
+    mem,
+    str,
+    io::{self, Read, Write, Seek, SeekFrom, BufWriter},
+    error::Error,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+    ops::{Index, IndexMut, Range}
+};
+
+use sdl2::pixels::Color;
+
+use ::image::{ExtendedColorType, ImageFormat};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{
+    Pos2,
+    Verbosity,
+    curve::{SpaceFillingCurve, next_power_of},
+    config::{CurveType, CropArea, AspectRatio, Channel, Endianness, Step}
+};
+
+
+#[derive(Debug)]
+pub enum ImageError
+{
+    Io(io::Error),
+    BadDimensions(String),
+    NotPowerOfTwo(usize),
+    NotPowerOfThree(usize),
+    OffsetOutOfRange{offset: usize, size: usize},
+    TrimOutOfRange{trimmed: usize, size: usize},
+    LengthOutOfRange{end: usize, size: usize},
+    CropOutOfRange{area: CropArea, size: Pos2<usize>},
+    BadMap(String),
+    BadPalette(String),
+    Decode(String),
+    // a warning that strict parsing turned into an error
+    Strict(String)
+}
+
+impl Display for ImageError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Self::Io(x) => write!(f, "{x}"),
+            Self::BadDimensions(x) => write!(f, "bad dimensions, {x}"),
+            Self::NotPowerOfTwo(x) => write!(f, "size must be a power of 2, got {x}"),
+            Self::NotPowerOfThree(x) => write!(f, "size must be a power of 3, got {x}"),
+            Self::OffsetOutOfRange{offset, size} =>
+            {
+                write!(f, "pixel offset ({offset}) leaves no bytes of file size ({size})")
+            },
+            Self::TrimOutOfRange{trimmed, size} =>
+            {
+                let reason = if trimmed == size { "leaves no bytes of" } else { "exceeds" };
+
+                write!(f, "trim_start+trim_end ({trimmed}) {reason} pixel data size ({size})")
+            },
+            Self::LengthOutOfRange{end, size} =>
+            {
+                write!(f, "trim_start+length ({end}) exceeds pixel data size ({size})")
+            },
+            Self::CropOutOfRange{area, size} =>
+            {
+                write!(f, "crop area {area} doesnt fit inside a {}x{} image", size.x, size.y)
+            },
+            Self::BadMap(x) => write!(f, "bad permutation map, {x}"),
+            Self::BadPalette(x) => write!(f, "bad palette, {x}"),
+            Self::Decode(x) => write!(f, "cant decode the input, {x}"),
+            Self::Strict(x) => write!(f, "{x} (not allowed when strict)")
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+impl From<io::Error> for ImageError
+{
+    fn from(value: io::Error) -> Self
+    {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PixelFormat
+{
+    Grayscale,
+    Rgb,
+    Rgba
+}
+
+impl PixelFormat
+{
+    pub fn from_bpp(bpp: usize) -> Option<Self>
+    {
+        match bpp
+        {
+            1 => Some(Self::Grayscale),
+            3 => Some(Self::Rgb),
+            4 => Some(Self::Rgba),
+            _ => None
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool
+    {
+        matches!(self, Self::Rgba)
+    }
+
+    // grayscale keeps its value in every color channel so the red one stands in for it
+    pub fn channels(&self) -> &'static [Channel]
+    {
+        match self
+        {
+            Self::Grayscale => &[Channel::R],
+            Self::Rgb => &[Channel::R, Channel::G, Channel::B],
+            Self::Rgba => &[Channel::R, Channel::G, Channel::B, Channel::A]
+        }
+    }
+
+    pub fn bpp(&self) -> usize
+    {
+        match self
+        {
+            Self::Grayscale => 1,
+            Self::Rgb => 3,
+            Self::Rgba => 4
+        }
+    }
+
+    // missing channels (from a cut off last pixel) get filled with the background color
+    #[allow(clippy::suspicious_else_formatting)]
+    fn decode(&self, chunk: &[u8], c: Color) -> Color
+    {
+        match self
+        {
+            Self::Grayscale => Color::RGB(chunk[0], chunk[0], chunk[0]),
+            Self::Rgb | Self::Rgba =>
+            {
+                let r = chunk[0];
+                let g = chunk.get(1).copied().unwrap_or(c.g);
+                let b = chunk.get(2).copied().unwrap_or(c.b);
+
+                if let Self::Rgba = self
+                {
+                    let a = chunk.get(3).copied().unwrap_or(c.a);
+
+                    Color::RGBA(r, g, b, a)
+                } else
+                {
+                    Color::RGB(r, g, b)
+                }
+            }
+        }
+    }
+
+    fn encode(&self, c: Color) -> Vec<u8>
+    {
+        match self
+        {
+            Self::Grayscale => vec![c.r],
+            Self::Rgb => vec![c.r, c.g, c.b],
+            Self::Rgba => vec![c.r, c.g, c.b, c.a]
+        }
+    }
+}
+
+impl Endianness
+{
+    pub fn is_little(&self) -> bool
+    {
+        match self
+        {
+            Self::Le => true,
+            Self::Be => false,
+            Self::Native => cfg!(target_endian = "little")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SampleDepth
+{
+    Eight,
+    Sixteen(Endianness)
+}
+
+impl SampleDepth
+{
+    pub fn bytes(&self) -> usize
+    {
+        match self
+        {
+            Self::Eight => 1,
+            Self::Sixteen(_) => 2
+        }
+    }
+
+    // keeps the most significant byte of every sample
+    fn narrow(&self, chunk: &[u8]) -> Vec<u8>
+    {
+        match self
+        {
+            Self::Eight => chunk.to_vec(),
+            Self::Sixteen(endian) =>
+            {
+                let high = if endian.is_little() { 1 } else { 0 };
+
+                // a cut off sample only has the one byte left
+                chunk.chunks(2).map(|sample| *sample.get(high).unwrap_or(&sample[0])).collect()
+            }
+        }
+    }
+
+    fn widen(&self, bytes: Vec<u8>) -> Vec<u8>
+    {
+        match self
+        {
+            Self::Eight => bytes,
+            Self::Sixteen(endian) =>
+            {
+                bytes.into_iter().flat_map(|x|
+                {
+                    let x = x as u16 * 257;
+
+                    if endian.is_little() { x.to_le_bytes() } else { x.to_be_bytes() }
+                }).collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelStats
+{
+    pub name: String,
+    pub min: u8,
+    pub max: u8,
+    pub mean: f64,
+    // amount of values in each 32 wide bucket
+    pub histogram: [usize; 8]
+}
+
+impl ChannelStats
+{
+    fn new(name: String) -> Self
+    {
+        Self{name, min: u8::MAX, max: u8::MIN, mean: 0.0, histogram: [0; 8]}
+    }
+}
+
+impl Display for ChannelStats
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let total = self.histogram.iter().sum::<usize>().max(1);
+
+        let histogram = self.histogram.iter()
+            .map(|x| format!("{:.1}%", *x as f64 * 100.0 / total as f64))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        write!(
+            f,
+            "{}: min {}, max {}, mean {:.2}, histogram [{histogram}]",
+            self.name,
+            self.min,
+            self.max,
+            self.mean
+        )
+    }
+}
+
+// how the bytes of the input get turned into pixels
+#[derive(Debug, Copy, Clone)]
+pub struct Layout
+{
+    // byte where the pixel data starts, the trims only apply to whats after it
+    pub offset: usize,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    // used instead of a square when neither size is known
+    pub aspect: Option<AspectRatio>,
+    pub format: PixelFormat,
+    pub depth: SampleDepth,
+    // every byte is an index into this and the image becomes rgb
+    pub palette: Option<Palette>,
+    // parsed images are always stored row by row no matter how the input was
+    pub order: MajorOrder
+}
+
+// which way consecutive pixels go
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MajorOrder
+{
+    // left to right then top to bottom
+    Row,
+    // top to bottom then left to right
+    Column
+}
+
+impl MajorOrder
+{
+    pub fn to_index(&self, size: Pos2<usize>, pos: Pos2<usize>) -> usize
+    {
+        match self
+        {
+            Self::Row => pos.y * size.x + pos.x,
+            Self::Column => pos.x * size.y + pos.y
+        }
+    }
+
+    pub fn index_to_pos(&self, size: Pos2<usize>, index: usize) -> Pos2<usize>
+    {
+        match self
+        {
+            Self::Row => Pos2{x: index % size.x, y: index / size.x},
+            Self::Column => Pos2{x: index / size.y, y: index % size.y}
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Palette(pub [Color; 256]);
+
+// encoded image files that get decoded instead of read as raw bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileFormat
+{
+    Png,
+    // binary ppm or pgm
+    Pnm
+}
+
+impl FileFormat
+{
+    const PNG_MAGIC: &'static [u8] = b"\x89PNG\r\n\x1a\n";
+
+    // by extension first and by magic bytes otherwise, stdin is always raw
+    pub fn detect(path: &Path) -> Result<Option<Self>, ImageError>
+    {
+        if path == Path::new("-")
+        {
+            return Ok(None);
+        }
+
+        match path.extension().and_then(|x| x.to_str())
+        {
+            Some("png") => return Ok(Some(Self::Png)),
+            Some("ppm" | "pgm" | "pnm") => return Ok(Some(Self::Pnm)),
+            _ => ()
+        }
+
+        let mut magic = Vec::with_capacity(Self::PNG_MAGIC.len());
+        File::open(path)?.take(Self::PNG_MAGIC.len() as u64).read_to_end(&mut magic)?;
+
+        Ok(Self::from_magic(&magic))
+    }
+
+    fn from_magic(magic: &[u8]) -> Option<Self>
+    {
+        if magic.starts_with(Self::PNG_MAGIC)
+        {
+            return Some(Self::Png);
+        }
+
+        match magic
+        {
+            [b'P', b'5' | b'6', x, ..] if x.is_ascii_whitespace() => Some(Self::Pnm),
+            _ => None
+        }
+    }
+}
+
+impl Display for FileFormat
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Self::Png => write!(f, "png"),
+            Self::Pnm => write!(f, "ppm")
+        }
+    }
+}
+
+// how saving reorders the pixels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remap
+{
+    // already in its final order
+    None,
+    Curve(CurveType),
+    // every listed channel with its own curve, the rest stay in raster order
+    Channels(Vec<(Channel, CurveType)>)
+}
+
+impl Display for Remap
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Self::None => write!(f, "nothing"),
+            Self::Curve(curve) => write!(f, "{curve}"),
+            Self::Channels(curves) =>
+            {
+                let curves = curves.iter().map(|(channel, curve)| format!("{channel}-{curve}")).collect::<Vec<_>>();
+
+                write!(f, "{}", curves.join("+"))
+            }
+        }
+    }
+}
+
+// where the read bytes stop
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DataEnd
+{
+    // amount of bytes to skip at the end
+    Trim(usize),
+    // exact amount of bytes after trim_start
+    Length(usize)
+}
+
+// how much parsing trusts the given layout
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strictness
+{
+    // warns and pads
+    Lenient,
+    // every warning about a suspicious layout is an error
+    Strict,
+    // also errors unless the bytes fill the image exactly
+    Exact
+}
+
+pub struct Image
+{
+    pub data: Vec<Color>,
+    pub format: PixelFormat,
+    pub width: usize,
+    pub height: usize,
+    // how data is laid out, raw saves write it back the same way
+    pub order: MajorOrder,
+    pub background: Color,
+    // remaps only touch this channel if its set
+    pub channel: Option<Channel>,
+    // raw saves write samples with this depth
+    pub save_depth: SampleDepth,
+    // pixels parsing added to fill out the image
+    pub padding: usize,
+    // size before getting padded by hilbertify
+    original: Option<Pos2<usize>>
+}
+
+impl Image
+{
+    pub fn parse(
+        path: impl AsRef<Path>,
+        layout: Layout,
+        c: Color,
+        trim_start: usize,
+        end: DataEnd,
+        strictness: Strictness,
+        verbosity: Verbosity
+    ) -> Result<Self, ImageError>
+    {
+        Self::parse_many(&[path], layout, c, trim_start, end, strictness, verbosity)
+    }
+
+    // reads the files one after another as if they were a single input
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn parse_many(
+        paths: &[impl AsRef<Path>],
+        layout: Layout,
+        c: Color,
+        trim_start: usize,
+        end: DataEnd,
+        strictness: Strictness,
+        verbosity: Verbosity
+    ) -> Result<Self, ImageError>
+    {
+        let Layout{offset, width, height, aspect, format, depth, palette, order} = layout;
+
+        if width == Some(0) || height == Some(0)
+        {
+            return Err(ImageError::BadDimensions("width and height must be at least 1".to_owned()));
+        }
+
+        let (format, bpp) = match palette
+        {
+            Some(_) => (PixelFormat::Rgb, 1),
+            None => (format, format.bpp() * depth.bytes())
+        };
+
+        // when both sizes are known theres no need to read past the end of the image,
+        // the size of the whole input still gets checked against it
+        let needed = width.zip(height).map(|(width, height)| width * height * bpp);
+
+        let paths = paths.iter().map(|path| path.as_ref()).collect::<Vec<_>>();
+
+        let (values, available) = Self::read_input(&paths, offset, trim_start, end, needed, verbosity)?;
+
+        if values.is_empty()
+        {
+            return Err(ImageError::BadDimensions("no bytes left to read after trimming".to_owned()));
+        }
+
+        // usually means the trims are off by a few bytes
+        // exact sizes get checked once the dimensions are known
+        let trailing = values.len() % bpp;
+        if trailing != 0 && strictness != Strictness::Exact
+        {
+            let message = format!("the last {trailing} bytes dont make a whole {bpp} byte pixel");
+            if strictness == Strictness::Strict
+            {
+                return Err(ImageError::Strict(message));
+            }
+
+            verbosity.info(format!("warning: {message}, padded it with the background"));
+        }
+
+        let mut data: Vec<Color> = values.chunks(bpp).map(|chunk|
+        {
+            match &palette
+            {
+                Some(palette) => palette.0[chunk[0] as usize],
+                None => format.decode(&depth.narrow(chunk), c)
+            }
+        }).collect();
+
+        let (width, height) = match (width, height)
+        {
+            (Some(width), Some(height)) =>
+            {
+                let expected = width * height * bpp;
+                if expected != available
+                {
+                    return Err(ImageError::BadDimensions(format!(
+                        "{width}x{height} image needs {expected} bytes, got {available}"
+                    )));
+                }
+
+                (width, height)
+            },
+            (Some(width), None) => (width, data.len().div_ceil(width)),
+            (None, Some(height)) => (data.len().div_ceil(height), height),
+            (None, None) => if let Some(aspect) = aspect
+            {
+                let size = Self::aspect_size(data.len(), aspect);
+
+                verbosity.info(format!("chose {}x{} for the {aspect} aspect ratio", size.x, size.y));
+
+                (size.x, size.y)
+            } else
+            {
+                let width = Self::square_width(data.len())?;
+
+                verbosity.verbose(format!("inferred dimensions: {width}x{width}"));
+
+                (width, width)
+            }
+        };
+
+        let total = width * height;
+
+        if strictness == Strictness::Exact && total * bpp != available
+        {
+            return Err(ImageError::BadDimensions(format!(
+                "{width}x{height} image needs exactly {} bytes, got {available} (padding and leftovers not allowed when exact)",
+                total * bpp
+            )));
+        }
+
+        if total < data.len()
+        {
+            panic!("total should never be less than len so far");
+        }
+
+        verbosity.verbose(format!("total amount of pixels: {}, total amount of bytes: {}", total, total * bpp));
+
+        // a partial last row is normal, more than that usually means the width is wrong
+        let padded = total - data.len();
+        if padded > width
+        {
+            let message = format!("padded {padded} pixels (>1 row)");
+            if strictness >= Strictness::Strict
+            {
+                return Err(ImageError::Strict(message));
+            }
+
+            verbosity.verbose(format!("warning: {message}; width may be incorrect"));
+        }
+
+        data.resize(total, c);
+
+        Ok(Self{
+            data,
+            format,
+            width,
+            height,
+            order,
+            background: c,
+            channel: None,
+            save_depth: SampleDepth::Eight,
+            padding: padded,
+            original: None
+        })
+    }
+
+    // decodes a png or ppm, none of the raw layout options apply to these
+    pub fn from_image_file(path: impl AsRef<Path>, file_format: FileFormat, c: Color) -> Result<Self, ImageError>
+    {
+        let bytes = fs::read(path)?;
+
+        let (width, height, format, values) = match file_format
+        {
+            FileFormat::Png => Self::decode_png(&bytes)?,
+            FileFormat::Pnm => Self::decode_pnm(&bytes)?
+        };
+
+        if width == 0 || height == 0
+        {
+            return Err(ImageError::Decode(format!("{width}x{height} image is empty")));
+        }
+
+        let data = values.chunks(format.bpp()).map(|chunk| format.decode(chunk, c)).collect();
+
+        Ok(Self{
+            data,
+            format,
+            width,
+            height,
+            order: MajorOrder::Row,
+            background: c,
+            channel: None,
+            save_depth: SampleDepth::Eight,
+            padding: 0,
+            original: None
+        })
+    }
+
+    fn decode_png(bytes: &[u8]) -> Result<(usize, usize, PixelFormat, Vec<u8>), ImageError>
+    {
+        let image = ::image::load_from_memory_with_format(bytes, ImageFormat::Png)
+            .map_err(|err| ImageError::Decode(err.to_string()))?;
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+
+        let color = image.color();
+
+        // everything gets narrowed to 8 bits per channel
+        let (format, values) = match (color.has_color(), color.has_alpha())
+        {
+            (false, false) => (PixelFormat::Grayscale, image.into_luma8().into_raw()),
+            (true, false) => (PixelFormat::Rgb, image.into_rgb8().into_raw()),
+            (_, true) => (PixelFormat::Rgba, image.into_rgba8().into_raw())
+        };
+
+        Ok((width, height, format, values))
+    }
+
+    // P5 or P6 with a maxval up to 65535
+    fn decode_pnm(bytes: &[u8]) -> Result<(usize, usize, PixelFormat, Vec<u8>), ImageError>
+    {
+        let error = |message: &str| ImageError::Decode(message.to_owned());
+
+        let format = match bytes.get(..2)
+        {
+            Some(b"P5") => PixelFormat::Grayscale,
+            Some(b"P6") => PixelFormat::Rgb,
+            _ => return Err(error("only binary ppm (P6) and pgm (P5) are supported"))
+        };
+
+        let mut position = 2;
+        let mut next_number = ||
+        {
+            loop
+            {
+                match bytes.get(position)
+                {
+                    Some(b'#') =>
+                    {
+                        while bytes.get(position).is_some_and(|x| *x != b'\n')
+                        {
+                            position += 1;
+                        }
+                    },
+                    Some(x) if x.is_ascii_whitespace() => position += 1,
+                    _ => break
+                }
+            }
+
+            let start = position;
+            while bytes.get(position).is_some_and(u8::is_ascii_digit)
+            {
+                position += 1;
+            }
+
+            str::from_utf8(&bytes[start..position]).ok()
+                .and_then(|x| x.parse::<usize>().ok())
+                .ok_or_else(|| error("bad header"))
+        };
+
+        let width = next_number()?;
+        let height = next_number()?;
+        let maxval = next_number()?;
+
+        if maxval == 0 || maxval > u16::MAX as usize
+        {
+            return Err(error("maxval must be between 1 and 65535"));
+        }
+
+        // a single whitespace byte separates the header from the samples
+        let start = position + 1;
+
+        let sample_bytes = if maxval < 256 { 1 } else { 2 };
+
+        // the header is untrusted, a huge size could wrap around and pass the bounds check
+        let too_large = || error("header dimensions too large");
+        let size = width.checked_mul(height)
+            .and_then(|x| x.checked_mul(format.bpp() * sample_bytes))
+            .ok_or_else(too_large)?;
+
+        let end = start.checked_add(size).ok_or_else(too_large)?;
+
+        let samples = bytes.get(start..end).ok_or_else(||
+        {
+            ImageError::Decode(format!("{width}x{height} image needs {size} bytes of samples"))
+        })?;
+
+        let values = samples.chunks(sample_bytes).map(|sample|
+        {
+            let value = sample.iter().fold(0, |acc, x| (acc << 8) | *x as usize);
+
+            (value * 255 / maxval) as u8
+        }).collect();
+
+        Ok((width, height, format, values))
+    }
+
+    pub fn stats(&self) -> Vec<ChannelStats>
+    {
+        let channels = self.format.channels();
+
+        let grayscale = self.format == PixelFormat::Grayscale;
+
+        let mut stats = channels.iter().map(|channel|
+        {
+            ChannelStats::new(if grayscale { "gray".to_owned() } else { channel.to_string() })
+        }).collect::<Vec<_>>();
+
+        let mut sums = vec![0_u64; channels.len()];
+
+        self.data.iter().for_each(|c|
+        {
+            channels.iter().zip(stats.iter_mut()).zip(sums.iter_mut()).for_each(|((channel, stats), sum)|
+            {
+                let value = channel.get(*c);
+
+                stats.min = stats.min.min(value);
+                stats.max = stats.max.max(value);
+                stats.histogram[value as usize / 32] += 1;
+
+                *sum += value as u64;
+            });
+        });
+
+        stats.iter_mut().zip(sums).for_each(|(stats, sum)|
+        {
+            stats.mean = sum as f64 / self.data.len().max(1) as f64;
+        });
+
+        stats
+    }
+
+    // filled with the background color
+    pub fn new(width: usize, height: usize, format: PixelFormat, c: Color) -> Self
+    {
+        Self{
+            data: vec![c; width * height],
+            format,
+            width,
+            height,
+            order: MajorOrder::Row,
+            background: c,
+            channel: None,
+            save_depth: SampleDepth::Eight,
+            padding: 0,
+            original: None
+        }
+    }
+
+    // brightness goes up along the curve so the curve shape is visible
+    pub fn generate(
+        curve: CurveType,
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+        c: Color
+    ) -> Result<Self, ImageError>
+    {
+        let mut image = Self::new(width, height, format, c);
+
+        if curve.needs_square()
+        {
+            image.check_square()?;
+        }
+
+        let total = width * height;
+        let last = total.saturating_sub(1).max(1);
+
+        let curve = curve.curve(width, height)?;
+        (0..total).for_each(|i|
+        {
+            let value = (i * 255 / last) as u8;
+
+            image[curve.value_to_point(i)] = Color::RGB(value, value, value);
+        });
+
+        Ok(image)
+    }
+
+    // a path of - reads from stdin, files only read the trimmed bytes up to the limit,
+    // skips to the pixel offset first and then trims the pixel data,
+    // also gives back how many bytes there were after trimming
+    #[allow(clippy::suspicious_else_formatting)]
+    fn read_input(
+        paths: &[&Path],
+        offset: usize,
+        trim_start: usize,
+        end: DataEnd,
+        limit: Option<usize>,
+        verbosity: Verbosity
+    ) -> Result<(Vec<u8>, usize), ImageError>
+    {
+        let start = offset.saturating_add(trim_start);
+
+        let check_trim = |size: usize|
+        {
+            if size == 0
+            {
+                return Err(ImageError::BadDimensions("the input is empty".to_owned()));
+            }
+
+            if offset >= size
+            {
+                return Err(ImageError::OffsetOutOfRange{offset, size});
+            }
+
+            let size = size - offset;
+
+            let available = match end
+            {
+                DataEnd::Trim(trim_end) =>
+                {
+                    let trimmed = trim_start.saturating_add(trim_end);
+                    if trimmed >= size
+                    {
+                        return Err(ImageError::TrimOutOfRange{trimmed, size});
+                    }
+
+                    size - trimmed
+                },
+                DataEnd::Length(length) =>
+                {
+                    let end = trim_start.saturating_add(length);
+                    if end > size
+                    {
+                        return Err(ImageError::LengthOutOfRange{end, size});
+                    }
+
+                    length
+                }
+            };
+            Ok((limit.map_or(available, |limit| limit.min(available)), available))
+        };
+
+        if paths == [Path::new("-")]
+        {
+            let mut values = Vec::new();
+            io::stdin().lock().read_to_end(&mut values)?;
+
+            let (amount, available) = check_trim(values.len())?;
+
+            values.truncate(start + amount);
+            values.drain(..start);
+
+            Ok((values, available))
+        } else
+        {
+            let sizes = paths.iter().map(|path|
+            {
+                Ok(fs::metadata(path)?.len() as usize)
+            }).collect::<Result<Vec<_>, ImageError>>()?;
+
+            let total = sizes.iter().sum();
+
+            if paths.len() > 1
+            {
+                verbosity.verbose(format!("concatenated {} files into {total} bytes", paths.len()));
+            }
+
+            let (amount, available) = check_trim(total)?;
+
+            let mut values = Vec::with_capacity(amount);
+
+            // only the files overlapping the wanted range get opened
+            let mut file_start = 0;
+            for (path, size) in paths.iter().zip(sizes)
+            {
+                let from = start.max(file_start);
+                let to = (start + amount).min(file_start + size);
+
+                if from < to
+                {
+                    let mut file = File::open(path)?;
+                    file.seek(SeekFrom::Start((from - file_start) as u64))?;
+
+                    file.take((to - from) as u64).read_to_end(&mut values)?;
+                }
+
+                file_start += size;
+            }
+
+            Ok((values, available))
+        }
+    }
+
+    // classic hex and ascii dump of the start of the input, for picking trims
+    pub fn hex_dump(path: impl AsRef<Path>, amount: usize) -> Result<String, ImageError>
+    {
+        let (bytes, _) = Self::read_input(&[path.as_ref()], 0, 0, DataEnd::Trim(0), Some(amount), Verbosity::Quiet)?;
+
+        Ok(Self::format_hex(&bytes))
+    }
+
+    fn format_hex(bytes: &[u8]) -> String
+    {
+        const ROW: usize = 16;
+
+        bytes.chunks(ROW).enumerate().map(|(row, chunk)|
+        {
+            let hex = (0..ROW).map(|index|
+            {
+                let separator = if index == ROW / 2 { "  " } else { " " };
+
+                let value = chunk.get(index).map(|x| format!("{x:02x}")).unwrap_or_else(|| "  ".to_owned());
+
+                format!("{separator}{value}")
+            }).collect::<String>();
+
+            let ascii = chunk.iter().map(|x|
+            {
+                if x.is_ascii_graphic() || *x == b' ' { *x as char } else { '.' }
+            }).collect::<String>();
+
+            format!("{:08x} {hex}  |{ascii}|\n", row * ROW)
+        }).collect()
+    }
+
+    // closest size to the ratio that fits all the pixels
+    fn aspect_size(pixels: usize, aspect: AspectRatio) -> Pos2<usize>
+    {
+        let ratio = aspect.0.x as f64 / aspect.0.y as f64;
+
+        let ideal = (pixels as f64 * ratio).sqrt();
+
+        // the ceiled height can skew the ratio so both neighbors of the ideal width get tried
+        let candidates = [ideal.floor() as usize, ideal.ceil() as usize];
+
+        candidates.into_iter().map(|width|
+        {
+            let width = width.max(1);
+
+            Pos2{x: width, y: pixels.div_ceil(width).max(1)}
+        }).min_by(|a, b|
+        {
+            let error = |size: &Pos2<usize>| (size.x as f64 / size.y as f64 - ratio).abs();
+
+            error(a).total_cmp(&error(b))
+        }).unwrap()
+    }
+
+    fn square_width(pixels: usize) -> Result<usize, ImageError>
+    {
+        let width = pixels.isqrt();
+
+        if width * width != pixels
+        {
+            return Err(ImageError::BadDimensions(
+                format!("cant infer a square size from {pixels} pixels, provide a width")
+            ));
+        }
+
+        Ok(width)
+    }
+
+    pub fn save_remapped(mut self, remap: &Remap, path: impl AsRef<Path>) -> Result<(), ImageError>
+    {
+        self.remap(remap)?;
+
+        self.save(path)
+    }
+
+    pub fn remap(&mut self, remap: &Remap) -> Result<(), ImageError>
+    {
+        match remap
+        {
+            Remap::None => Ok(()),
+            Remap::Curve(curve) => self.hilbertify(*curve),
+            Remap::Channels(curves) => self.hilbertify_channels(curves)
+        }
+    }
+
+    // key=value lines that --from-meta reads back, the transform history only goes into comments
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn meta(&self, remap: &Remap, transforms: &[Step]) -> String
+    {
+        let transforms = if transforms.is_empty()
+        {
+            "none".to_owned()
+        } else
+        {
+            transforms.iter().map(|step| step.to_string()).collect::<Vec<_>>().join(" -> ")
+        };
+
+        let c = self.background;
+
+        let mut lines = vec![
+            format!("# transforms: {transforms}"),
+            format!("# remapped with: {remap}"),
+            format!("width={}", self.width),
+            format!("height={}", self.height),
+            format!("bpp={}", self.format.bpp()),
+            format!("background={:02x}{:02x}{:02x}", c.r, c.g, c.b)
+        ];
+
+        if self.order == MajorOrder::Column
+        {
+            lines.push("column-major=true".to_owned());
+        }
+
+        if let SampleDepth::Sixteen(endian) = self.save_depth
+        {
+            lines.push("bit-depth=16".to_owned());
+            lines.push(format!("endian={endian}"));
+        }
+
+        match remap
+        {
+            Remap::None => (),
+            Remap::Curve(curve) =>
+            {
+                lines.push("unhilbertify=true".to_owned());
+                lines.push(format!("curve={curve}"));
+
+                if let Some(channel) = self.channel
+                {
+                    lines.push(format!("channel={channel}"));
+                }
+            },
+            Remap::Channels(curves) =>
+            {
+                lines.push("unhilbertify=true".to_owned());
+                lines.extend(curves.iter().map(|(channel, curve)| format!("curve-{channel}={curve}")));
+            }
+        }
+
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ImageError>
+    {
+        let path = path.as_ref();
+
+        if path == Path::new("-")
+        {
+            return self.write_raw(io::stdout().lock());
+        }
+
+        match path.extension().and_then(|x| x.to_str())
+        {
+            Some("png") => self.save_png(path),
+            Some("ppm") => self.save_ppm(path),
+            _ => self.save_raw(path)
+        }
+    }
+
+    pub fn save_raw(&self, path: impl AsRef<Path>) -> Result<(), ImageError>
+    {
+        self.write_raw(File::create(path)?)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), ImageError>
+    {
+        let color_type = match self.format
+        {
+            PixelFormat::Grayscale => ExtendedColorType::L8,
+            PixelFormat::Rgb => ExtendedColorType::Rgb8,
+            PixelFormat::Rgba => ExtendedColorType::Rgba8
+        };
+
+        let bytes = self.row_major().flat_map(|c| self.format.encode(c)).collect::<Vec<_>>();
+
+        ::image::save_buffer(
+            path,
+            &bytes,
+            self.width as u32,
+            self.height as u32,
+            color_type
+        ).map_err(|err| io::Error::other(err).into())
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> Result<(), ImageError>
+    {
+        let mut s = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+
+        s.extend(self.row_major().flat_map(|c| [c.r, c.g, c.b]));
+
+        Ok(fs::write(path, s)?)
+    }
+
+    // writes pixel by pixel so the whole encoded image never sits in memory
+    fn write_raw(&self, writer: impl Write) -> Result<(), ImageError>
+    {
+        let mut writer = BufWriter::new(writer);
+
+        self.data.iter().try_for_each(|c|
+        {
+            writer.write_all(&self.save_depth.widen(self.format.encode(*c)))
+        })?;
+
+        Ok(writer.flush()?)
+    }
+
+    // png and ppm only know rows
+    fn row_major(&self) -> impl Iterator<Item=Color> + '_
+    {
+        (0..self.data.len()).map(|index| self[MajorOrder::Row.index_to_pos(self.size(), index)])
+    }
+
+    #[cfg(test)]
+    fn raw_bytes(&self) -> Vec<u8>
+    {
+        self.data.iter().flat_map(|c|
+        {
+            self.format.encode(*c)
+        }).collect()
+    }
+
+    pub fn unhilbertify(&mut self, curve: CurveType) -> Result<(), ImageError>
+    {
+        if curve.needs_square()
+        {
+            self.check_square()?;
+        }
+
+        self.remap_positions(&*curve.curve(self.width, self.height)?, true);
+
+        if let Some(original) = self.original.take()
+        {
+            self.resize_canvas(original.x, original.y);
+        }
+
+        Ok(())
+    }
+
+    // pads the image to a power of 2 (or 3) square if the curve needs it, unhilbertify crops it back
+    pub fn hilbertify(&mut self, curve: CurveType) -> Result<(), ImageError>
+    {
+        if let Some(base) = curve.square_base()
+        {
+            let size = next_power_of(base, self.width.max(self.height));
+
+            if self.width != size || self.height != size
+            {
+                self.original = Some(Pos2{x: self.width, y: self.height});
+
+                self.resize_canvas(size, size);
+            }
+        }
+
+        self.remap_positions(&*curve.curve(self.width, self.height)?, false);
+
+        Ok(())
+    }
+
+    // remaps each listed channel with its own curve, the other channels stay in raster order
+    pub fn hilbertify_channels(&mut self, curves: &[(Channel, CurveType)]) -> Result<(), ImageError>
+    {
+        if let Some(size) = Self::shared_square(self.width, self.height, curves)?
+        {
+            if self.width != size || self.height != size
+            {
+                self.original = Some(Pos2{x: self.width, y: self.height});
+
+                self.resize_canvas(size, size);
+            }
+        }
+
+        self.remap_each_channel(curves, false)
+    }
+
+    pub fn unhilbertify_channels(&mut self, curves: &[(Channel, CurveType)]) -> Result<(), ImageError>
+    {
+        if curves.iter().any(|(_, curve)| curve.needs_square())
+        {
+            self.check_square()?;
+        }
+
+        self.remap_each_channel(curves, true)?;
+
+        if let Some(original) = self.original.take()
+        {
+            self.resize_canvas(original.x, original.y);
+        }
+
+        Ok(())
+    }
+
+    fn remap_each_channel(&mut self, curves: &[(Channel, CurveType)], inverse: bool) -> Result<(), ImageError>
+    {
+        let previous = self.channel;
+
+        let result = curves.iter().try_for_each(|(channel, curve)|
+        {
+            self.channel = Some(*channel);
+
+            self.remap_positions(&*curve.curve(self.width, self.height)?, inverse);
+
+            Ok(())
+        });
+
+        self.channel = previous;
+
+        result
+    }
+
+    // square size that works for every curve, none if none of them need a square
+    fn shared_square(width: usize, height: usize, curves: &[(Channel, CurveType)]) -> Result<Option<usize>, ImageError>
+    {
+        let mut sizes = curves.iter()
+            .filter_map(|(_, curve)| curve.square_base())
+            .map(|base| next_power_of(base, width.max(height)));
+
+        let Some(size) = sizes.next() else
+        {
+            return Ok(None);
+        };
+
+        if sizes.any(|x| x != size)
+        {
+            return Err(ImageError::BadDimensions(
+                format!("the channel curves need different square sizes for a {width}x{height} image")
+            ));
+        }
+
+        Ok(Some(size))
+    }
+
+    // keeps the pixels in the top left, fills the new space with the background color
+    fn resize_canvas(&mut self, width: usize, height: usize)
+    {
+        let mut data = vec![self.background; width * height];
+
+        for y in 0..self.height.min(height)
+        {
+            for x in 0..self.width.min(width)
+            {
+                let pos = Pos2{x, y};
+
+                data[self.order.to_index(Pos2{x: width, y: height}, pos)] = self[pos];
+            }
+        }
+
+        self.data = data;
+        self.width = width;
+        self.height = height;
+    }
+
+    fn check_square(&self) -> Result<(), ImageError>
+    {
+        if self.width != self.height
+        {
+            return Err(ImageError::BadDimensions(
+                format!("expected a square image, got {}x{}", self.width, self.height)
+            ));
+        }
+
+        Ok(())
+    }
+
+    // moves pixels from raster order into curve order, or back if inverse
+    fn remap_positions(&mut self, curve: &dyn SpaceFillingCurve, inverse: bool)
+    {
+        let f = Self::curve_mapping(self.size(), self.order, curve, inverse);
+
+        #[cfg(feature = "rayon")]
+        self.remap_with_parallel(f);
+
+        #[cfg(not(feature = "rayon"))]
+        self.remap_channel(f);
+    }
+
+    // new position of every index
+    #[allow(clippy::suspicious_else_formatting)]
+    fn curve_mapping(
+        size: Pos2<usize>,
+        order: MajorOrder,
+        curve: &dyn SpaceFillingCurve,
+        inverse: bool
+    ) -> impl Fn(usize) -> usize + Sync + '_
+    {
+        move |index|
+        {
+            if inverse
+            {
+                order.to_index(size, curve.value_to_point(index))
+            } else
+            {
+                curve.point_to_value(order.index_to_pos(size, index))
+            }
+        }
+    }
+
+    // destination of every pixel when hilbertifying, including the padding
+    pub fn permutation(&self, curve: CurveType) -> Result<Vec<usize>, ImageError>
+    {
+        let (width, height) = match curve.square_base()
+        {
+            Some(base) =>
+            {
+                let size = next_power_of(base, self.width.max(self.height));
+
+                (size, size)
+            },
+            None => (self.width, self.height)
+        };
+
+        let curve = curve.curve(width, height)?;
+
+        let size = Pos2{x: width, y: height};
+
+        Ok((0..width * height).map(Self::curve_mapping(size, self.order, &*curve, false)).collect())
+    }
+
+    // moves every pixel to the index at its position in the map, maps from padded
+    // images pad this one the same way
+    pub fn apply_map(&mut self, map: &[usize]) -> Result<(), ImageError>
+    {
+        let side = map.len().isqrt();
+        if map.len() > self.data.len() && side * side == map.len() && side >= self.width.max(self.height)
+        {
+            self.original = Some(Pos2{x: self.width, y: self.height});
+
+            self.resize_canvas(side, side);
+        }
+
+        let total = self.data.len();
+        if map.len() != total
+        {
+            return Err(ImageError::BadMap(format!("has {} indices, image has {total} pixels", map.len())));
+        }
+
+        let mut seen = vec![false; total];
+        map.iter().try_for_each(|index|
+        {
+            match seen.get_mut(*index)
+            {
+                Some(x) if !*x =>
+                {
+                    *x = true;
+
+                    Ok(())
+                },
+                Some(_) => Err(ImageError::BadMap(format!("index {index} shows up more than once"))),
+                None => Err(ImageError::BadMap(format!("index {index} is out of range")))
+            }
+        })?;
+
+        self.remap_channel(|index| map[index]);
+
+        Ok(())
+    }
+
+    // the curve evaluations are the slow part so only those run in parallel
+    #[cfg(feature = "rayon")]
+    pub(crate) fn remap_with_parallel(&mut self, f: impl Fn(usize) -> usize + Sync)
+    {
+        let positions: Vec<usize> = (0..self.data.len()).into_par_iter().map(&f).collect();
+
+        self.remap_channel(|index| positions[index]);
+    }
+
+    // moves the pixel at each index to the index f gives back
+    pub(crate) fn remap_with(&mut self, f: impl FnMut(usize) -> usize)
+    {
+        self.data = remap_values(mem::take(&mut self.data), self.background, f);
+    }
+
+    // only moves the selected channel around if theres one
+    fn remap_channel(&mut self, f: impl FnMut(usize) -> usize)
+    {
+        let channel = match self.channel
+        {
+            Some(x) => x,
+            None => return self.remap_with(f)
+        };
+
+        let plane = self.data.iter().map(|c| channel.get(*c)).collect::<Vec<_>>();
+
+        remap_values(plane, 0, f).into_iter().zip(self.data.iter_mut()).for_each(|(value, c)|
+        {
+            channel.set(c, value);
+        });
+    }
+
+    // alpha stays the same
+    pub fn invert(&mut self)
+    {
+        self.data.iter_mut().for_each(|c|
+        {
+            *c = Color::RGBA(255 - c.r, 255 - c.g, 255 - c.b, c.a);
+        });
+    }
+
+    // out = 255 * (v / 255) ^ (1 / gamma), alpha stays the same
+    pub fn apply_gamma(&mut self, gamma: f32)
+    {
+        let table: Vec<u8> = (0..=255).map(|v|
+        {
+            let value = 255.0 * (v as f32 / 255.0).powf(1.0 / gamma);
+
+            value.round().clamp(0.0, 255.0) as u8
+        }).collect();
+
+        self.data.iter_mut().for_each(|c|
+        {
+            *c = Color::RGBA(table[c.r as usize], table[c.g as usize], table[c.b as usize], c.a);
+        });
+    }
+
+    // snaps every color channel to the closest of the evenly spaced levels
+    pub fn quantize(&mut self, levels: u8)
+    {
+        let steps = levels.max(2) as u32 - 1;
+
+        let snap = |v: u8| ((v as u32 * steps + 127) / 255 * 255 / steps) as u8;
+
+        self.data.iter_mut().for_each(|c|
+        {
+            *c = Color::RGBA(snap(c.r), snap(c.g), snap(c.b), c.a);
+        });
+    }
+
+    // the new red channel comes from order[0] and so on
+    pub fn swap_channels(&mut self, order: [Channel; 3])
+    {
+        self.data.iter_mut().for_each(|c|
+        {
+            let [r, g, b] = order.map(|channel| channel.get(*c));
+
+            *c = Color::RGBA(r, g, b, c.a);
+        });
+    }
+
+    pub fn flip_horizontal(&mut self)
+    {
+        let (size, order) = (self.size(), self.order);
+
+        self.remap_with(|index|
+        {
+            let pos = order.index_to_pos(size, index);
+
+            order.to_index(size, Pos2{x: size.x - 1 - pos.x, y: pos.y})
+        });
+    }
+
+    pub fn flip_vertical(&mut self)
+    {
+        let (size, order) = (self.size(), self.order);
+
+        self.remap_with(|index|
+        {
+            let pos = order.index_to_pos(size, index);
+
+            order.to_index(size, Pos2{x: pos.x, y: size.y - 1 - pos.y})
+        });
+    }
+
+    pub fn crop(&mut self, x: usize, y: usize, width: usize, height: usize) -> Result<(), ImageError>
+    {
+        let fits = |start: usize, size: usize, image_size: usize|
+        {
+            size != 0 && start.checked_add(size).is_some_and(|end| end <= image_size)
+        };
+
+        if !fits(x, width, self.width) || !fits(y, height, self.height)
+        {
+            let area = CropArea{pos: Pos2{x, y}, size: Pos2{x: width, y: height}};
+
+            return Err(ImageError::CropOutOfRange{area, size: Pos2{x: self.width, y: self.height}});
+        }
+
+        self.data = (0..width * height).map(|index|
+        {
+            let pos = self.order.index_to_pos(Pos2{x: width, y: height}, index);
+
+            self[Pos2{x, y} + pos]
+        }).collect();
+
+        self.width = width;
+        self.height = height;
+
+        Ok(())
+    }
+
+    // crops the biggest centered power of 2 square, returns its size
+    pub fn crop_power_of_two_square(&mut self) -> Result<usize, ImageError>
+    {
+        let side = self.width.min(self.height);
+        if side == 0
+        {
+            return Err(ImageError::BadDimensions(
+                format!("cant fit a square inside a {}x{} image", self.width, self.height)
+            ));
+        }
+
+        let size = 1 << side.ilog2();
+
+        self.crop((self.width - size) / 2, (self.height - size) / 2, size, size)?;
+
+        Ok(size)
+    }
+
+    // nearest neighbor
+    pub fn resize(&mut self, width: usize, height: usize)
+    {
+        self.data = self.resized_data(width, height);
+
+        self.width = width;
+        self.height = height;
+    }
+
+    // nearest neighbor
+    fn resized_data(&self, width: usize, height: usize) -> Vec<Color>
+    {
+        (0..width * height).map(|index|
+        {
+            let pos = self.order.index_to_pos(Pos2{x: width, y: height}, index);
+
+            self[Pos2{x: pos.x * self.width / width, y: pos.y * self.height / height}]
+        }).collect()
+    }
+
+    // smaller copy with the longest side at most max_side, for showing huge images quickly
+    pub fn downscaled(&self, max_side: usize) -> Self
+    {
+        let longest = self.width.max(self.height);
+
+        let scaled = |side: usize| if longest > max_side { (side * max_side / longest).max(1) } else { side };
+
+        let (width, height) = (scaled(self.width), scaled(self.height));
+
+        Self{
+            data: self.resized_data(width, height),
+            format: self.format,
+            width,
+            height,
+            order: self.order,
+            background: self.background,
+            channel: self.channel,
+            save_depth: self.save_depth,
+            padding: 0,
+            original: None
+        }
+    }
+
+    // clockwise
+    pub fn rotate90(&mut self, times: u8)
+    {
+        for _ in 0..(times % 4)
+        {
+            let (size, order) = (self.size(), self.order);
+
+            self.remap_with(|index|
+            {
+                let pos = order.index_to_pos(size, index);
+
+                order.to_index(Pos2{x: size.y, y: size.x}, Pos2{x: size.y - 1 - pos.y, y: pos.x})
+            });
+
+            self.width = size.y;
+            self.height = size.x;
+        }
+    }
+
+    pub fn size(&self) -> Pos2<usize>
+    {
+        Pos2{x: self.width, y: self.height}
+    }
+
+    pub fn to_index(&self, pos: Pos2<usize>) -> usize
+    {
+        self.order.to_index(self.size(), pos)
+    }
+
+    pub fn index_to_pos(&self, index: usize) -> Pos2<usize>
+    {
+        self.order.index_to_pos(self.size(), index)
+    }
+
+    pub fn to_index_checked(&self, pos: Pos2<usize>) -> Option<usize>
+    {
+        (pos.x < self.width && pos.y < self.height).then(|| self.to_index(pos))
+    }
+
+    pub fn get(&self, pos: Pos2<usize>) -> Option<&Color>
+    {
+        self.to_index_checked(pos).map(|index| &self.data[index])
+    }
+
+    fn out_of_bounds(&self, pos: Pos2<usize>) -> !
+    {
+        panic!("position {}x{} is outside of a {}x{} image", pos.x, pos.y, self.width, self.height)
+    }
+
+    // row major, for anything that isnt laid out like an image
+    pub fn to_index_assoc(width: usize, pos: Pos2<usize>) -> usize
+    {
+        pos.y * width + pos.x
+    }
+
+    pub fn index_to_pos_assoc(width: usize, index: usize) -> Pos2<usize>
+    {
+        Pos2{
+            x: index % width,
+            y: index / width
+        }
+    }
+
+    // the first index that doesnt come back the same after going to a position and back
+    pub fn index_round_trip(width: usize, indices: Range<usize>) -> Result<(), usize>
+    {
+        indices.into_iter().find(|index|
+        {
+            let pos = Self::index_to_pos_assoc(width, *index);
+
+            pos.x >= width || Self::to_index_assoc(width, pos) != *index
+        }).map_or(Ok(()), Err)
+    }
+}
+
+// every position gets written since f is a permutation
+fn remap_values<T: Copy>(values: Vec<T>, fill: T, mut f: impl FnMut(usize) -> usize) -> Vec<T>
+{
+    let mut output = vec![fill; values.len()];
+
+    values.into_iter().enumerate().for_each(|(i, value)|
+    {
+        let new_position = f(i);
+
+        output[new_position] = value;
+    });
+
+    output
+}
+
+impl Channel
+{
+    fn get(&self, c: Color) -> u8
+    {
+        match self
+        {
+            Self::R => c.r,
+            Self::G => c.g,
+            Self::B => c.b,
+            Self::A => c.a
+        }
+    }
+
+    fn set(&self, c: &mut Color, value: u8)
+    {
+        match self
+        {
+            Self::R => c.r = value,
+            Self::G => c.g = value,
+            Self::B => c.b = value,
+            Self::A => c.a = value
+        }
+    }
+}
+
+impl Index<Pos2<usize>> for Image
+{
+    type Output = Color;
+
+    fn index(&self, index: Pos2<usize>) -> &Self::Output
+    {
+        self.get(index).unwrap_or_else(|| self.out_of_bounds(index))
+    }
+}
+
+impl IndexMut<Pos2<usize>> for Image
+{
+    fn index_mut(&mut self, index: Pos2<usize>) -> &mut Self::Output
+    {
+        match self.to_index_checked(index)
+        {
+            Some(x) => &mut self.data[x],
+            None => self.out_of_bounds(index)
+        }
+    }
+}
+
+// the sidecar sits next to the output with .meta appended, like foo.raw.meta
+pub fn meta_path(path: impl AsRef<Path>) -> PathBuf
+{
+    let mut path = path.as_ref().as_os_str().to_owned();
+    path.push(".meta");
+
+    PathBuf::from(path)
+}
+
+#[allow(clippy::suspicious_else_formatting)]
+pub fn save_map(map: &[usize], path: impl AsRef<Path>) -> Result<(), ImageError>
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    let wide = map.len() > u32::MAX as usize + 1;
+
+    map.iter().try_for_each(|index|
+    {
+        if wide
+        {
+            writer.write_all(&(*index as u64).to_le_bytes())
+        } else
+        {
+            writer.write_all(&(*index as u32).to_le_bytes())
+        }
+    })?;
+
+    Ok(writer.flush()?)
+}
+
+// reads the format save_map writes, u64s only get used for more indices than u32s can hold
+#[allow(clippy::suspicious_else_formatting)]
+pub fn load_map(path: impl AsRef<Path>) -> Result<Vec<usize>, ImageError>
+{
+    let bytes = fs::read(path)?;
+
+    let wide = bytes.len() / 4 > u32::MAX as usize + 1;
+    let size = if wide { 8 } else { 4 };
+
+    if bytes.len() % size != 0
+    {
+        return Err(ImageError::BadMap(format!("{} bytes isnt a multiple of {size}", bytes.len())));
+    }
+
+    Ok(bytes.chunks(size).map(|x|
+    {
+        if wide
+        {
+            u64::from_le_bytes(x.try_into().unwrap()) as usize
+        } else
+        {
+            u32::from_le_bytes(x.try_into().unwrap()) as usize
+        }
+    }).collect())
+}
+
+// 256 rgb triplets
+pub fn load_palette(path: impl AsRef<Path>) -> Result<Palette, ImageError>
+{
+    let bytes = fs::read(path)?;
+
+    if bytes.len() != 256 * 3
+    {
+        return Err(ImageError::BadPalette(format!("must be exactly 768 bytes, got {}", bytes.len())));
+    }
+
+    let mut colors = [Color::RGB(0, 0, 0); 256];
+    colors.iter_mut().zip(bytes.chunks(3)).for_each(|(color, rgb)|
+    {
+        *color = Color::RGB(rgb[0], rgb[1], rgb[2]);
+    });
+
+    Ok(Palette(colors))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::env;
+
+    use crate::curve::GrayRowCurve;
+
+    fn test_image(width: usize, height: usize) -> Image
+    {
+        let data = (0..width * height).map(|i|
+        {
+            Color::RGB(i as u8, (i >> 8) as u8, (i >> 16) as u8)
+        }).collect();
+
+        Image{
+            data,
+            format: PixelFormat::Rgb,
+            width,
+            height,
+            order: MajorOrder::Row,
+            background: Color::RGB(0, 0, 0),
+            channel: None,
+            save_depth: SampleDepth::Eight,
+            padding: 0,
+            original: None
+        }
+    }
+
+    // 8 bit row major pixels right at the start, struct update syntax covers the rest
+    fn layout(width: Option<usize>, height: Option<usize>, format: PixelFormat) -> Layout
+    {
+        Layout{
+            offset: 0,
+            width,
+            height,
+            aspect: None,
+            format,
+            depth: SampleDepth::Eight,
+            palette: None,
+            order: MajorOrder::Row
+        }
+    }
+
+    // an input for the parsing tests, each test removes its own files
+    fn input_file(name: &str, bytes: &[u8]) -> PathBuf
+    {
+        let path = env::temp_dir().join(format!("imagedisplay_{name}"));
+        fs::write(&path, bytes).unwrap();
+
+        path
+    }
+
+    #[test]
+    fn gray_rows()
+    {
+        let width = 7;
+        let height = 13;
+
+        let curve: &dyn SpaceFillingCurve = &GrayRowCurve::new(width, height);
+
+        let total = width * height;
+        for i in 0..total
+        {
+            let point = curve.value_to_point(i);
+
+            assert_eq!(curve.point_to_value(point), i);
+        }
+
+        let original = test_image(width, height);
+
+        let mut image = test_image(width, height);
+        image.hilbertify(CurveType::Grayrow).unwrap();
+
+        // every row moves as a whole so columns stay intact
+        (0..height).for_each(|y|
+        {
+            let row = (0..height).find(|row| original[Pos2{x: 0, y: *row}] == image[Pos2{x: 0, y}])
+                .unwrap();
+
+            (0..width).for_each(|x| assert_eq!(image[Pos2{x, y}], original[Pos2{x, y: row}]));
+        });
+
+        assert_eq!(image[Pos2{x: 0, y: 2}], original[Pos2{x: 0, y: 3}]);
+    }
+
+    #[test]
+    fn channel_stats()
+    {
+        let mut image = test_image(4, 2);
+        image.format = PixelFormat::Grayscale;
+
+        let stats = image.stats();
+
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].min, stats[0].max), (0, 7));
+        assert_eq!(stats[0].mean, 3.5);
+        assert_eq!(stats[0].histogram, [8, 0, 0, 0, 0, 0, 0, 0]);
+
+        let stats = test_image(300, 1).stats();
+
+        assert_eq!(stats.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), ["r", "g", "b"]);
+        assert_eq!((stats[1].min, stats[1].max), (0, 1));
+        assert_eq!(stats[0].histogram.iter().sum::<usize>(), 300);
+    }
+
+    #[test]
+    fn single_channel()
+    {
+        let original = test_image(16, 16);
+
+        let mut image = test_image(16, 16);
+        image.channel = Some(Channel::R);
+
+        image.hilbertify(CurveType::Hilbert).unwrap();
+
+        assert_ne!(image.data, original.data);
+        image.data.iter().zip(original.data.iter()).for_each(|(c, original)|
+        {
+            assert_eq!((c.g, c.b), (original.g, original.b));
+        });
+
+        image.unhilbertify(CurveType::Hilbert).unwrap();
+
+        assert_eq!(image.data, original.data);
+    }
+
+    #[test]
+    fn inverting()
+    {
+        let original = test_image(5, 3);
+
+        let mut image = test_image(5, 3);
+        image.invert();
+
+        assert_eq!(image[Pos2{x: 2, y: 0}], Color::RGB(253, 255, 255));
+
+        image.invert();
+
+        assert_eq!(image.data, original.data);
+    }
+
+    #[test]
+    fn gamma()
+    {
+        let original = test_image(16, 16);
+
+        let mut image = test_image(16, 16);
+        image.apply_gamma(1.0);
+
+        assert_eq!(image.data, original.data);
+
+        image.apply_gamma(2.0);
+
+        assert_eq!(image[Pos2{x: 0, y: 4}].r, 128);
+        assert_eq!(image[Pos2{x: 15, y: 15}].r, 255);
+        assert_eq!(image[Pos2{x: 0, y: 0}].r, 0);
+    }
+
+    #[test]
+    fn quantizing()
+    {
+        let mut image = test_image(16, 16);
+        image.quantize(2);
+
+        assert!(image.data.iter().all(|c| [0, 255].contains(&c.r)));
+        assert_eq!(image[Pos2{x: 15, y: 7}].r, 0);
+        assert_eq!(image[Pos2{x: 0, y: 8}].r, 255);
+
+        let mut image = test_image(16, 16);
+        image.quantize(3);
+
+        assert_eq!(image[Pos2{x: 4, y: 4}].r, 127);
+
+        let original = test_image(16, 16);
+
+        image.data.clone_from(&original.data);
+        image.quantize(255);
+
+        assert!(image.data.iter().zip(original.data.iter()).all(|(a, b)| a.r.abs_diff(b.r) <= 1));
+    }
+
+    #[test]
+    fn swapping_channels()
+    {
+        let mut image = test_image(1, 1);
+        image.data[0] = Color::RGBA(1, 2, 3, 4);
+
+        image.swap_channels([Channel::B, Channel::G, Channel::R]);
+        assert_eq!(image.data[0], Color::RGBA(3, 2, 1, 4));
+
+        image.swap_channels([Channel::G, Channel::B, Channel::R]);
+        assert_eq!(image.data[0], Color::RGBA(2, 1, 3, 4));
+    }
+
+    #[test]
+    fn flips()
+    {
+        let original = test_image(5, 3);
+
+        let mut image = test_image(5, 3);
+        image.flip_horizontal();
+
+        assert_eq!(image[Pos2{x: 0, y: 1}], original[Pos2{x: 4, y: 1}]);
+
+        image.flip_vertical();
+
+        assert_eq!(image[Pos2{x: 0, y: 0}], original[Pos2{x: 4, y: 2}]);
+
+        image.flip_horizontal();
+        image.flip_vertical();
+
+        assert_eq!(image.data, original.data);
+    }
+
+    #[test]
+    fn rotations()
+    {
+        let original = test_image(2, 3);
+
+        let mut image = test_image(2, 3);
+        image.rotate90(1);
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image[Pos2{x: 2, y: 0}], original[Pos2{x: 0, y: 0}]);
+        assert_eq!(image[Pos2{x: 0, y: 1}], original[Pos2{x: 1, y: 2}]);
+
+        (0..3).for_each(|_| image.rotate90(1));
+
+        assert_eq!((image.width, image.height), (2, 3));
+        assert_eq!(image.data, original.data);
+
+        image.rotate90(2);
+        image.rotate90(2);
+
+        assert_eq!(image.data, original.data);
+    }
+
+    #[test]
+    fn resizing()
+    {
+        let original = test_image(3, 2);
+
+        let mut image = test_image(3, 2);
+        image.resize(6, 4);
+
+        assert_eq!(image[Pos2{x: 5, y: 3}], original[Pos2{x: 2, y: 1}]);
+        assert_eq!(image[Pos2{x: 2, y: 1}], original[Pos2{x: 1, y: 0}]);
+
+        image.resize(3, 2);
+
+        assert_eq!(image.data, original.data);
+    }
+
+    #[test]
+    fn index_round_trips()
+    {
+        [1, 2, 3, 7, 8, 13, 640].into_iter().for_each(|width|
+        {
+            // the partial last row included
+            let height = 5;
+            assert_eq!(Image::index_round_trip(width, 0..width * height + width / 2), Ok(()), "width {width}");
+
+            let last = width * height - 1;
+            assert_eq!(Image::index_to_pos_assoc(width, last), Pos2{x: width - 1, y: height - 1});
+        });
+
+        assert_eq!(Image::index_to_pos_assoc(7, 15), Pos2{x: 1, y: 2});
+        assert_eq!(Image::to_index_assoc(7, Pos2{x: 6, y: 0}), 6);
+    }
+
+    #[test]
+    fn major_orders()
+    {
+        let size = Pos2{x: 5, y: 3};
+
+        [MajorOrder::Row, MajorOrder::Column].into_iter().for_each(|order|
+        {
+            (0..size.x * size.y).for_each(|index|
+            {
+                let pos = order.index_to_pos(size, index);
+
+                assert!(pos.x < size.x && pos.y < size.y);
+                assert_eq!(order.to_index(size, pos), index);
+            });
+        });
+
+        assert_eq!(MajorOrder::Row.to_index(size, Pos2{x: 1, y: 2}), 11);
+        assert_eq!(MajorOrder::Column.to_index(size, Pos2{x: 1, y: 2}), 5);
+
+        // columns of 3 with the last one missing a pixel
+        let path = input_file("column_major.raw", &[0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let layout = Layout{order: MajorOrder::Column, ..layout(None, Some(3), PixelFormat::Grayscale)};
+
+        let image = Image::parse(&path, layout, Color::RGB(9, 9, 9), 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+        assert_eq!((image.width, image.height), (3, 3));
+
+        // stays in the input order, positions still go across rows
+        assert_eq!(image.raw_bytes(), [0, 1, 2, 3, 4, 5, 6, 7, 9]);
+        assert_eq!(image.row_major().map(|c| c.r).collect::<Vec<_>>(), [0, 3, 6, 1, 4, 7, 2, 5, 9]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn major_order_round_trips()
+    {
+        let bytes = (0..8 * 4 * 3).map(|x| x as u8).collect::<Vec<u8>>();
+
+        let path = input_file("order_round_trip.raw", &bytes);
+        let saved = env::temp_dir().join("imagedisplay_order_round_trip_saved.raw");
+        let png = env::temp_dir().join("imagedisplay_order_round_trip.png");
+
+        let black = Color::RGB(0, 0, 0);
+
+        [MajorOrder::Row, MajorOrder::Column].into_iter().for_each(|order|
+        {
+            let layout = Layout{order, ..layout(Some(8), Some(4), PixelFormat::Rgb)};
+
+            let parse = |path: &Path| Image::parse(path, layout, black, 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+            let image = parse(&path);
+
+            image.save(&saved).unwrap();
+            assert_eq!(fs::read(&saved).unwrap(), bytes);
+
+            // the same pixels laid out in rows
+            let mut rows = Image::new(8, 4, PixelFormat::Rgb, black);
+            (0..8 * 4).map(|index| image.index_to_pos(index)).for_each(|pos| rows[pos] = image[pos]);
+
+            image.save(&png).unwrap();
+            assert_eq!(Image::from_image_file(&png, FileFormat::Png, black).unwrap().data, rows.data);
+
+            let same_pixels = |a: &Image, b: &Image|
+            {
+                assert_eq!(a.size(), b.size());
+                (0..a.data.len()).map(|index| a.index_to_pos(index)).for_each(|pos| assert_eq!(a[pos], b[pos]));
+            };
+
+            let mut image = image;
+
+            [CurveType::Hilbert, CurveType::Morton, CurveType::Snake].into_iter().for_each(|curve|
+            {
+                let original = image.data.clone();
+
+                // curve order doesnt depend on the layout, the pixels come out the same
+                image.hilbertify(curve).unwrap();
+                rows.hilbertify(curve).unwrap();
+                assert_eq!(image.data, rows.data);
+
+                image.unhilbertify(curve).unwrap();
+                rows.unhilbertify(curve).unwrap();
+                same_pixels(&image, &rows);
+
+                assert_eq!(image.data, original);
+            });
+
+            image.rotate90(1);
+            image.flip_horizontal();
+            image.crop(1, 2, 3, 4).unwrap();
+
+            rows.rotate90(1);
+            rows.flip_horizontal();
+            rows.crop(1, 2, 3, 4).unwrap();
+
+            same_pixels(&image, &rows);
+        });
+
+        [path, saved, png].into_iter().for_each(|path| fs::remove_file(path).unwrap());
+    }
+
+    #[test]
+    fn downscaled()
+    {
+        let image = test_image(300, 100);
+
+        let preview = image.downscaled(30);
+
+        assert_eq!((preview.width, preview.height), (30, 10));
+        assert_eq!(preview[Pos2{x: 1, y: 1}], image[Pos2{x: 10, y: 10}]);
+
+        let same = image.downscaled(300);
+
+        assert_eq!((same.width, same.height), (300, 100));
+        assert_eq!(same.data, image.data);
+
+        assert_eq!(test_image(1000, 2).downscaled(10).height, 1);
+    }
+
+    #[test]
+    fn checked_indexing()
+    {
+        let image = test_image(3, 2);
+
+        assert_eq!(image.to_index_checked(Pos2{x: 2, y: 1}), Some(5));
+        assert_eq!(image.to_index_checked(Pos2{x: 3, y: 0}), None);
+        assert_eq!(image.to_index_checked(Pos2{x: 0, y: 2}), None);
+
+        assert_eq!(image.get(Pos2{x: 1, y: 1}), Some(&image.data[4]));
+        assert_eq!(image.get(Pos2{x: 5, y: 5}), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of a 3x2 image")]
+    fn index_out_of_bounds()
+    {
+        let _ = test_image(3, 2)[Pos2{x: 3, y: 0}];
+    }
+
+    #[test]
+    fn cropping()
+    {
+        let original = test_image(5, 4);
+
+        let mut image = test_image(5, 4);
+        image.crop(1, 2, 3, 2).unwrap();
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image[Pos2{x: 0, y: 0}], original[Pos2{x: 1, y: 2}]);
+        assert_eq!(image[Pos2{x: 2, y: 1}], original[Pos2{x: 3, y: 3}]);
+
+        assert!(matches!(test_image(5, 4).crop(3, 0, 3, 1), Err(ImageError::CropOutOfRange{..})));
+        assert!(matches!(test_image(5, 4).crop(0, 0, 0, 1), Err(ImageError::CropOutOfRange{..})));
+        assert!(test_image(5, 4).crop(0, 0, 5, 4).is_ok());
+    }
+
+    #[test]
+    fn power_of_two_square()
+    {
+        let original = test_image(300, 200);
+
+        let mut image = test_image(300, 200);
+
+        assert_eq!(image.crop_power_of_two_square().unwrap(), 128);
+        assert_eq!((image.width, image.height), (128, 128));
+        assert_eq!(image[Pos2{x: 0, y: 0}], original[Pos2{x: 86, y: 36}]);
+
+        let mut image = test_image(64, 64);
+
+        assert_eq!(image.crop_power_of_two_square().unwrap(), 64);
+        assert_eq!(image.data, test_image(64, 64).data);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_remap()
+    {
+        use crate::curve::HilbertCurve;
+
+        let size = 64;
+
+        let curve = HilbertCurve::new(size).unwrap();
+        let f = |index| curve.point_to_value(Image::index_to_pos_assoc(size, index));
+
+        let mut serial = test_image(size, size);
+        serial.remap_with(f);
+
+        let mut parallel = test_image(size, size);
+        parallel.remap_with_parallel(f);
+
+        assert_eq!(serial.data, parallel.data);
+    }
+
+    #[test]
+    fn reads_only_needed_bytes()
+    {
+        let path = input_file("partial_read.raw", &(0..=255).collect::<Vec<u8>>());
+
+        let black = Color::RGB(0, 0, 0);
+        let square = |size| layout(Some(size), Some(size), PixelFormat::Grayscale);
+
+        let image = Image::parse(&path, square(2), black, 10, DataEnd::Trim(242), Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+        assert_eq!(image.raw_bytes(), [10, 11, 12, 13]);
+
+        let image = Image::parse(&path, Layout{offset: 100, ..square(2)}, black, 10, DataEnd::Trim(142), Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+        assert_eq!(image.raw_bytes(), [110, 111, 112, 113]);
+
+        // reading less doesnt mean the extra bytes get ignored when both sizes are given
+        let error = Image::parse(&path, square(2), black, 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::BadDimensions(_))));
+
+        let error = Image::parse(&path, square(2), black, 10, DataEnd::Length(5), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::BadDimensions(_))));
+
+        let error = Image::parse(&path, Layout{offset: 250, ..square(2)}, black, 3, DataEnd::Trim(3), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::TrimOutOfRange{trimmed: 6, size: 6})));
+
+        let error = Image::parse(&path, Layout{offset: 256, ..square(2)}, black, 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::OffsetOutOfRange{offset: 256, size: 256})));
+
+        let error = Image::parse(&path, square(16), black, 1, DataEnd::Trim(1), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::BadDimensions(_))));
+
+        let image = Image::parse(&path, square(2), black, 20, DataEnd::Length(4), Strictness::Lenient, Verbosity::Quiet).unwrap();
+        assert_eq!(image.raw_bytes(), [20, 21, 22, 23]);
+
+        let length = layout(None, None, PixelFormat::Grayscale);
+        let image = Image::parse(&path, length, black, 200, DataEnd::Length(9), Strictness::Lenient, Verbosity::Quiet).unwrap();
+        assert_eq!(image.raw_bytes(), (200..209).collect::<Vec<u8>>());
+
+        let error = Image::parse(&path, square(2), black, 250, DataEnd::Length(7), Strictness::Lenient, Verbosity::Quiet);
+        assert!(matches!(error, Err(ImageError::LengthOutOfRange{end: 257, size: 256})));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn concatenated_inputs()
+    {
+        let bytes = (0..30).collect::<Vec<u8>>();
+
+        let paths = bytes.chunks(10).enumerate().map(|(index, chunk)|
+        {
+            input_file(&format!("concat_{index}.raw"), chunk)
+        }).collect::<Vec<_>>();
+
+        let layout = Layout{offset: 2, ..layout(Some(4), None, PixelFormat::Grayscale)};
+
+        let parse = |trim_start, end| Image::parse_many(&paths, layout, Color::RGB(0, 0, 0), trim_start, end, Strictness::Lenient, Verbosity::Quiet);
+
+        // crosses both file boundaries
+        let image = parse(6, DataEnd::Length(16)).unwrap();
+        assert_eq!(image.raw_bytes(), (8..24).collect::<Vec<u8>>());
+
+        let image = parse(0, DataEnd::Trim(4)).unwrap();
+        assert_eq!(image.raw_bytes(), (2..26).collect::<Vec<u8>>());
+
+        let error = parse(0, DataEnd::Length(29));
+        assert!(matches!(error, Err(ImageError::LengthOutOfRange{end: 29, size: 28})));
+
+        paths.into_iter().for_each(|path| fs::remove_file(path).unwrap());
+    }
+
+    #[test]
+    fn strict_parsing()
+    {
+        // 5 whole rgb pixels and 2 trailing bytes
+        let path = input_file("strict.raw", &(0..17).collect::<Vec<u8>>());
+
+        let parse = |width, height, trim_end, strictness|
+        {
+            let layout = layout(width, height, PixelFormat::Rgb);
+
+            Image::parse(&path, layout, Color::RGB(0, 0, 0), 0, DataEnd::Trim(trim_end), strictness, Verbosity::Quiet)
+        };
+
+        let (lenient, strict, exact) = (Strictness::Lenient, Strictness::Strict, Strictness::Exact);
+
+        assert!(parse(Some(2), None, 0, lenient).is_ok());
+        assert!(matches!(parse(Some(2), None, 0, strict), Err(ImageError::Strict(_))));
+
+        // a partial last row is fine but more padding than that isnt
+        assert!(parse(Some(2), None, 2, strict).is_ok());
+
+        assert!(parse(None, Some(4), 2, lenient).is_ok());
+        assert!(matches!(parse(None, Some(4), 2, strict), Err(ImageError::Strict(_))));
+
+        // exact wont pad even a partial row or skip the bytes past the image
+        assert!(parse(Some(5), None, 2, exact).is_ok());
+        assert!(parse(Some(5), Some(1), 2, exact).is_ok());
+
+        assert!(matches!(parse(Some(5), None, 0, exact), Err(ImageError::BadDimensions(_))));
+        assert!(matches!(parse(Some(2), None, 2, exact), Err(ImageError::BadDimensions(_))));
+
+        // both sizes given always have to match the input
+        assert!(matches!(parse(Some(2), Some(2), 2, lenient), Err(ImageError::BadDimensions(_))));
+        assert!(matches!(parse(Some(2), Some(2), 2, exact), Err(ImageError::BadDimensions(_))));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn empty_dimensions()
+    {
+        let path = input_file("empty_dimensions.raw", &[1, 2, 3, 4, 5, 6]);
+
+        let parse = |width, height, end|
+        {
+            Image::parse(&path, layout(width, height, PixelFormat::Rgb), Color::RGB(0, 0, 0), 0, end, Strictness::Lenient, Verbosity::Quiet)
+        };
+
+        [(Some(0), None), (None, Some(0)), (Some(0), Some(2))].into_iter().for_each(|(width, height)|
+        {
+            let result = parse(width, height, DataEnd::Trim(0));
+            assert!(matches!(result, Err(ImageError::BadDimensions(_))), "{width:?}x{height:?} should fail");
+        });
+
+        let result = parse(None, None, DataEnd::Length(0));
+        assert!(matches!(result, Err(ImageError::BadDimensions(_))));
+
+        fs::write(&path, []).unwrap();
+
+        let result = parse(None, None, DataEnd::Trim(0));
+        assert!(matches!(result, Err(ImageError::BadDimensions(_))));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn image_file_input()
+    {
+        let image = test_image(5, 3);
+
+        let png = env::temp_dir().join("imagedisplay_input.png");
+        let ppm = env::temp_dir().join("imagedisplay_input.ppm");
+        let renamed = env::temp_dir().join("imagedisplay_input_png.bin");
+
+        image.save(&png).unwrap();
+        image.save(&ppm).unwrap();
+
+        fs::copy(&png, &renamed).unwrap();
+
+        let black = Color::RGB(0, 0, 0);
+        [(&png, FileFormat::Png), (&ppm, FileFormat::Pnm), (&renamed, FileFormat::Png)].into_iter()
+            .for_each(|(path, format)|
+            {
+                assert_eq!(FileFormat::detect(path).unwrap(), Some(format));
+
+                let decoded = Image::from_image_file(path, format, black).unwrap();
+
+                assert_eq!((decoded.width, decoded.height, decoded.format), (5, 3, PixelFormat::Rgb));
+                assert_eq!(decoded.data, image.data);
+            });
+
+        // 16 bit gray with a comment in the header
+        fs::write(&ppm, b"P5 # comment\n2 1\n65535\n\xff\xff\x80\x00").unwrap();
+
+        let decoded = Image::from_image_file(&ppm, FileFormat::Pnm, black).unwrap();
+        assert_eq!(decoded.raw_bytes(), [255, 127]);
+
+        fs::write(&ppm, b"P6\n2 2\n255\n\x00").unwrap();
+        assert!(matches!(Image::from_image_file(&ppm, FileFormat::Pnm, black), Err(ImageError::Decode(_))));
+
+        fs::write(&renamed, b"P7\nraw").unwrap();
+        assert_eq!(FileFormat::detect(&renamed).unwrap(), None);
+
+        [png, ppm, renamed].into_iter().for_each(|path| fs::remove_file(path).unwrap());
+    }
+
+    #[test]
+    fn oversized_pnm_header()
+    {
+        let header = b"P6 4294967296 4294967296 255\n\x00\x00\x00";
+        assert!(matches!(Image::decode_pnm(header), Err(ImageError::Decode(_))));
+
+        let header = format!("P5 {} 1 255\n", usize::MAX).into_bytes();
+        assert!(matches!(Image::decode_pnm(&header), Err(ImageError::Decode(_))));
+    }
+
+    #[test]
+    fn hex_dump()
+    {
+        let bytes = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDRabc";
+
+        let expected = "00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n\
+            00000010  61 62 63                                          |abc|\n";
+
+        assert_eq!(Image::format_hex(bytes), expected);
+    }
+
+    #[test]
+    fn aspect_sizes()
+    {
+        let size = |pixels, x, y| Image::aspect_size(pixels, AspectRatio(Pos2{x, y}));
+
+        assert_eq!(size(1920 * 1080, 16, 9), Pos2{x: 1920, y: 1080});
+        assert_eq!(size(100, 1, 1), Pos2{x: 10, y: 10});
+        assert_eq!(size(50, 2, 1), Pos2{x: 10, y: 5});
+        assert_eq!(size(1000, 4, 3), Pos2{x: 37, y: 28});
+        assert_eq!(size(3, 1, 100), Pos2{x: 1, y: 3});
+    }
+
+    #[test]
+    fn palette_input()
+    {
+        let palette = (0..=255).flat_map(|x: u8| [x, !x, x / 2]).collect::<Vec<u8>>();
+
+        let palette_path = input_file("palette.pal", &palette);
+        let path = input_file("palette.raw", &[0, 1, 255, 128]);
+
+        let layout = Layout{
+            palette: Some(load_palette(&palette_path).unwrap()),
+            ..layout(Some(2), Some(2), PixelFormat::Grayscale)
+        };
+
+        let image = Image::parse(&path, layout, Color::RGB(0, 0, 0), 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+        assert_eq!(image.format, PixelFormat::Rgb);
+        assert_eq!(image.raw_bytes(), [0, 255, 0, 1, 254, 0, 255, 0, 127, 128, 127, 64]);
+
+        fs::write(&palette_path, &palette[..767]).unwrap();
+        assert!(matches!(load_palette(&palette_path), Err(ImageError::BadPalette(_))));
+
+        fs::remove_file(palette_path).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_remapped()
+    {
+        let path = env::temp_dir().join("imagedisplay_save_remapped.raw");
+
+        let image = test_image(5, 3);
+
+        let mut expected = test_image(5, 3);
+        expected.hilbertify(CurveType::Hilbert).unwrap();
+
+        image.save_remapped(&Remap::Curve(CurveType::Hilbert), &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), expected.raw_bytes());
+
+        test_image(5, 3).save_remapped(&Remap::None, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), test_image(5, 3).raw_bytes());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_parse_round_trip()
+    {
+        let path = env::temp_dir().join("imagedisplay_round_trip.raw");
+
+        let background = Color::RGBA(1, 2, 3, 4);
+
+        [PixelFormat::Grayscale, PixelFormat::Rgb, PixelFormat::Rgba].into_iter().for_each(|format|
+        {
+            let (width, height) = (7, 5);
+
+            let bytes = (0..width * height * format.bpp()).map(|x| (x * 37) as u8).collect::<Vec<u8>>();
+
+            let mut image = Image::new(width, height, format, background);
+            image.data = bytes.chunks(format.bpp()).map(|chunk| format.decode(chunk, background)).collect();
+
+            image.save(&path).unwrap();
+            assert_eq!(fs::read(&path).unwrap(), bytes);
+
+            let layout = layout(Some(width), None, format);
+
+            let parse = |end| Image::parse(&path, layout, background, 0, end, Strictness::Lenient, Verbosity::Quiet).unwrap();
+
+            let parsed = parse(DataEnd::Trim(0));
+
+            assert_eq!((parsed.width, parsed.height), (width, height));
+            assert_eq!(parsed.data, image.data);
+
+            // the missing pixels of the last row get padded back with the background
+            let missing = 3;
+            let parsed = parse(DataEnd::Trim(missing * format.bpp()));
+
+            assert_eq!((parsed.width, parsed.height), (width, height));
+
+            assert_eq!(parsed.padding, missing);
+
+            let kept = width * height - missing;
+            assert_eq!(parsed.data[..kept], image.data[..kept]);
+            assert!(parsed.data[kept..].iter().all(|pixel| *pixel == background));
+        });
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sixteen_bit()
+    {
+        let path = input_file("sixteen_bit.raw", &[0x12, 0x34, 0xab, 0xcd, 0xff, 0x00, 0x01, 0x02]);
+
+        let black = Color::RGB(0, 0, 0);
+        let parse = |depth|
+        {
+            let layout = Layout{depth, ..layout(Some(2), Some(2), PixelFormat::Grayscale)};
+
+            Image::parse(&path, layout, black, 0, DataEnd::Trim(0), Strictness::Lenient, Verbosity::Quiet).unwrap()
+        };
+
+        let mut image = parse(SampleDepth::Sixteen(Endianness::Be));
+        assert_eq!(image.raw_bytes(), [0x12, 0xab, 0xff, 0x01]);
+
+        let image_le = parse(SampleDepth::Sixteen(Endianness::Le));
+        assert_eq!(image_le.raw_bytes(), [0x34, 0xcd, 0x00, 0x02]);
+
+        let image_native = parse(SampleDepth::Sixteen(Endianness::Native));
+        let expected = if cfg!(target_endian = "little") { &image_le } else { &image };
+        assert_eq!(image_native.raw_bytes(), expected.raw_bytes());
+
+        image.save_depth = SampleDepth::Sixteen(Endianness::Le);
+        image.save_raw(&path).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), [0x12, 0x12, 0xab, 0xab, 0xff, 0xff, 0x01, 0x01]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn generated_pattern()
+    {
+        let mut image = Image::generate(CurveType::Hilbert, 16, 16, PixelFormat::Rgb, Color::RGB(0, 0, 0))
+            .unwrap();
+
+        assert_eq!(image[Pos2{x: 0, y: 0}], Color::RGB(0, 0, 0));
+        assert_eq!(image[Pos2{x: 15, y: 0}], Color::RGB(255, 255, 255));
+
+        image.hilbertify(CurveType::Hilbert).unwrap();
+
+        // back in raster order the brightness only goes up
+        assert!(image.data.windows(2).all(|pair| pair[0].r <= pair[1].r));
+
+        assert!(Image::generate(CurveType::Hilbert, 16, 8, PixelFormat::Rgb, Color::RGB(0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn permutation_matches_hilbertify()
+    {
+        let original = test_image(6, 5);
+
+        let map = original.permutation(CurveType::Hilbert).unwrap();
+        assert_eq!(map.len(), 64);
+
+        let mut image = test_image(6, 5);
+        image.hilbertify(CurveType::Hilbert).unwrap();
+
+        (0..5).for_each(|y|
+        {
+            (0..6).for_each(|x|
+            {
+                let index = Image::to_index_assoc(8, Pos2{x, y});
+
+                assert_eq!(image.data[map[index]], original[Pos2{x, y}]);
+            });
+        });
+    }
+
+    #[test]
+    fn applying_maps()
+    {
+        let original = test_image(6, 5);
+
+        let map = original.permutation(CurveType::Morton).unwrap();
+
+        let mut image = test_image(6, 5);
+        image.apply_map(&map).unwrap();
+
+        let mut expected = test_image(6, 5);
+        expected.hilbertify(CurveType::Morton).unwrap();
+
+        assert_eq!(image.data, expected.data);
+
+        assert!(matches!(image.apply_map(&[0, 1]), Err(ImageError::BadMap(_))));
+
+        let path = env::temp_dir().join("imagedisplay_map.bin");
+
+        save_map(&map, &path).unwrap();
+        assert_eq!(load_map(&path).unwrap(), map);
+
+        fs::remove_file(path).unwrap();
+
+        let mut image = test_image(2, 2);
+        assert!(matches!(image.apply_map(&[0, 1, 1, 3]), Err(ImageError::BadMap(_))));
+        assert!(matches!(image.apply_map(&[0, 1, 2, 4]), Err(ImageError::BadMap(_))));
+
+        image.apply_map(&[3, 2, 1, 0]).unwrap();
+        assert_eq!(image[Pos2{x: 1, y: 1}], test_image(2, 2)[Pos2{x: 0, y: 0}]);
+    }
+
+    // xorshift so the random images are the same every run
+    fn random_values(seed: u64) -> impl FnMut() -> u64
+    {
+        let mut state = seed.max(1);
+
+        move ||
+        {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            state
+        }
+    }
+
+    #[test]
+    fn hilbertify_round_trip_random()
+    {
+        let mut random = random_values(0x1234_5678);
+
+        let formats = [PixelFormat::Grayscale, PixelFormat::Rgb, PixelFormat::Rgba];
+        let curves = [CurveType::Hilbert, CurveType::Morton, CurveType::Peano, CurveType::Snake, CurveType::Grayrow];
+
+        (0..64).for_each(|_|
+        {
+            let size = 1 << (random() % 7);
+            let format = formats[random() as usize % formats.len()];
+
+            let mut image = Image::new(size, size, format, Color::RGB(0, 0, 0));
+            image.data.iter_mut().for_each(|pixel|
+            {
+                let bytes = random().to_le_bytes();
+
+                *pixel = format.decode(&bytes[..format.bpp()], Color::RGB(0, 0, 0));
+            });
+
+            curves.into_iter().for_each(|curve|
+            {
+                let original = image.data.clone();
+
+                image.hilbertify(curve).unwrap();
+                image.unhilbertify(curve).unwrap();
+
+                assert_eq!((image.width, image.height), (size, size), "{curve} changed the size");
+                assert!(image.data == original, "{curve} didnt restore a {size}x{size} {format:?} image");
+            });
+        });
+    }
+
+    #[test]
+    fn channel_curves()
+    {
+        let original = test_image(6, 5);
+
+        let curves = [(Channel::R, CurveType::Hilbert), (Channel::G, CurveType::Morton)];
+
+        let mut image = test_image(6, 5);
+        image.hilbertify_channels(&curves).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 8));
+
+        curves.into_iter().for_each(|(channel, curve)|
+        {
+            let mut single = test_image(6, 5);
+            single.channel = Some(channel);
+            single.hilbertify(curve).unwrap();
+
+            let plane = |image: &Image| image.data.iter().map(|c| channel.get(*c)).collect::<Vec<_>>();
+
+            assert_eq!(plane(&image), plane(&single), "{channel} should match a single {curve} remap");
+        });
+
+        let mut padded = test_image(6, 5);
+        padded.resize_canvas(8, 8);
+        assert!(image.data.iter().zip(&padded.data).all(|(a, b)| a.b == b.b), "blue should stay in raster order");
+
+        image.unhilbertify_channels(&curves).unwrap();
+
+        assert_eq!((image.width, image.height), (6, 5));
+        assert!(image.data == original.data);
+
+        let mixed = [(Channel::R, CurveType::Hilbert), (Channel::B, CurveType::Peano)];
+        assert!(matches!(test_image(5, 5).hilbertify_channels(&mixed), Err(ImageError::BadDimensions(_))));
+    }
+
+    #[test]
+    fn unhilbertify_errors()
+    {
+        assert!(matches!(test_image(4, 2).unhilbertify(CurveType::Hilbert), Err(ImageError::BadDimensions(_))));
+        assert!(matches!(test_image(3, 3).unhilbertify(CurveType::Hilbert), Err(ImageError::NotPowerOfTwo(3))));
+        assert!(test_image(4, 4).unhilbertify(CurveType::Hilbert).is_ok());
+    }
+
+    #[test]
+    fn hilbertify_padded()
+    {
+        let original = test_image(300, 200);
+
+        let curves = [
+            CurveType::Hilbert,
+            CurveType::Morton,
+            CurveType::Peano,
+            CurveType::Snake,
+            CurveType::Grayrow
+        ];
+
+        curves.into_iter().for_each(|curve|
+        {
+            let mut image = test_image(300, 200);
+            image.hilbertify(curve).unwrap();
+
+            match curve.square_base()
+            {
+                Some(2) => assert_eq!((image.width, image.height), (512, 512)),
+                Some(3) => assert_eq!((image.width, image.height), (729, 729)),
+                _ => ()
+            }
+
+            image.unhilbertify(curve).unwrap();
+
+            assert_eq!((image.width, image.height), (300, 200));
+            assert_eq!(image.data, original.data);
+        });
+    }
+}
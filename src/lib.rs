@@ -0,0 +1,138 @@
+use std::{
+    process,
+    fmt::Display,
+    num::TryFromIntError,
+    ops::{Add, Sub, Mul}
+};
+
+pub mod image;
+pub mod curve;
+pub mod config;
+
+
+// exit codes so scripts can tell what kind of failure happened
+pub const EXIT_BAD_ARGS: i32 = 2;
+pub const EXIT_IO: i32 = 3;
+pub const EXIT_BAD_LAYOUT: i32 = 4;
+
+// for bad arguments, other failures pick their code with complain_with
+pub fn complain(message: impl Display) -> !
+{
+    complain_with(message, EXIT_BAD_ARGS)
+}
+
+pub fn complain_with(message: impl Display, code: i32) -> !
+{
+    eprintln!("{message}");
+
+    process::exit(code)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity
+{
+    Quiet,
+    Normal,
+    Verbose
+}
+
+impl Verbosity
+{
+    // notices that only get hidden by --quiet
+    pub fn info(&self, message: impl Display)
+    {
+        if *self >= Self::Normal
+        {
+            eprintln!("{message}");
+        }
+    }
+
+    // diagnostics that need --verbose
+    pub fn verbose(&self, message: impl Display)
+    {
+        if *self >= Self::Verbose
+        {
+            eprintln!("{message}");
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pos2<T>
+{
+    pub x: T,
+    pub y: T
+}
+
+impl<T: Add<Output=T>> Add for Pos2<T>
+{
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output
+    {
+        Self{x: self.x + other.x, y: self.y + other.y}
+    }
+}
+
+impl<T: Sub<Output=T>> Sub for Pos2<T>
+{
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output
+    {
+        Self{x: self.x - other.x, y: self.y - other.y}
+    }
+}
+
+impl<T: Mul<Output=T> + Copy> Mul<T> for Pos2<T>
+{
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self::Output
+    {
+        Self{x: self.x * scalar, y: self.y * scalar}
+    }
+}
+
+// viewport math is signed but indexing isnt, fails if either coordinate is negative
+impl TryFrom<Pos2<i32>> for Pos2<usize>
+{
+    type Error = TryFromIntError;
+
+    fn try_from(value: Pos2<i32>) -> Result<Self, Self::Error>
+    {
+        Ok(Self{x: value.x.try_into()?, y: value.y.try_into()?})
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn pos_arithmetic()
+    {
+        let a = Pos2{x: 3, y: 5};
+        let b = Pos2{x: 1, y: 2};
+
+        assert_eq!(a + b, Pos2{x: 4, y: 7});
+        assert_eq!(a - b, Pos2{x: 2, y: 3});
+        assert_eq!(a * 3, Pos2{x: 9, y: 15});
+        assert_eq!((a - b) * 2 + b, Pos2{x: 5, y: 8});
+
+        assert_eq!(Pos2{x: -1, y: 4} + Pos2{x: 3, y: -6}, Pos2{x: 2, y: -2});
+    }
+
+    #[test]
+    fn signed_pos_conversion()
+    {
+        assert_eq!(Pos2::<usize>::try_from(Pos2{x: 3, y: 0}), Ok(Pos2{x: 3, y: 0}));
+        assert_eq!(Pos2::<usize>::try_from(Pos2{x: i32::MAX, y: 7}), Ok(Pos2{x: i32::MAX as usize, y: 7}));
+
+        [Pos2{x: -1, y: 0}, Pos2{x: 0, y: -1}, Pos2{x: -5, y: -5}, Pos2{x: i32::MIN, y: 2}].into_iter().for_each(|pos|
+        {
+            assert!(Pos2::<usize>::try_from(pos).is_err(), "{pos:?} shouldnt convert");
+        });
+    }
+}
@@ -2,397 +2,613 @@ use std::{
     fs,
     io,
     env,
-    thread,
-    process,
+    iter,
     fmt::Display,
-    path::Path,
-    time::Duration,
-    ops::{Index, IndexMut}
+    path::{Path, PathBuf}
 };
 
-use sdl2::{
-    EventPump,
-    rect::Rect,
-    pixels::Color,
-    event::Event,
-    video::Window
+use imagedisplay::{
+    Pos2,
+    Verbosity,
+    EXIT_IO,
+    EXIT_BAD_LAYOUT,
+    complain,
+    complain_with,
+    image::{Image, ImageError, Layout, MajorOrder, DataEnd, FileFormat, Remap, Strictness, save_map, load_map, load_palette, meta_path},
+    config::{Config, Step, size_from_name}
 };
 
-use config::Config;
+use window::{DrawerWindow, WindowSettings, Frames};
 
-mod config;
+mod window;
 
 
-pub fn complain(message: impl Display) -> !
+fn load(path: impl AsRef<Path>, config: &Config) -> Result<Image, ImageError>
 {
-    println!("{message}");
+    let path = path.as_ref();
 
-    process::exit(1)
-}
-
-struct DrawerWindow
-{
-    window: Window,
-    events: EventPump
-}
+    // appended files only make sense for raw dumps
+    let format = if config.append.is_empty() { FileFormat::detect(path)? } else { None };
 
-impl DrawerWindow
-{
-    pub fn new(image: Image) -> Self
+    if let Some(format) = format
     {
-        let ctx = sdl2::init().unwrap();
-
-        let video = ctx.video().unwrap();
-
-        let window = video.window("imagedisplay thingy!", image.width as u32, image.height as u32)
-            .build()
-            .unwrap();
+        config.verbosity.verbose(format!("decoding the input as a {format}, the raw layout options dont apply"));
 
-        let events = ctx.event_pump().unwrap();
-
-        let mut surface = window.surface(&events).unwrap();
-
-        let mut draw_pixel = |x, y, c|
-        {
-            surface.fill_rect(Rect::new(x as i32, y as i32, 1, 1), c).unwrap();
-        };
-
-        for (i, pixel) in image.data.into_iter().enumerate()
-        {
-            let x = i % image.width;
-            let y = i / image.width;
+        return Image::from_image_file(path, format, config.background);
+    }
 
-            draw_pixel(x, y, pixel);
-        }
+    let layout = raw_layout(path, config)?;
 
-        surface.update_window().unwrap();
+    let end = config.length.map_or(DataEnd::Trim(config.trim_end), DataEnd::Length);
 
-        Self{window, events}
-    }
+    Image::parse_many(
+        &input_paths(path, config),
+        layout,
+        config.background,
+        config.trim_start,
+        end,
+        config.strictness(),
+        config.verbosity
+    )
+}
 
-    pub fn wait_exit(mut self)
+fn exit_code(err: &ImageError) -> i32
+{
+    match err
     {
-        loop
-        {
-            for event in self.events.poll_iter()
-            {
-                match event
-                {
-                    Event::Quit{..} => return,
-                    _ => ()
-                }
-            }
-
-            let surface = self.window.surface(&self.events).unwrap();
+        ImageError::Io(_)
+        | ImageError::Decode(_)
+        | ImageError::BadMap(_)
+        | ImageError::BadPalette(_) => EXIT_IO,
+        ImageError::BadDimensions(_)
+        | ImageError::NotPowerOfTwo(_)
+        | ImageError::NotPowerOfThree(_)
+        | ImageError::OffsetOutOfRange{..}
+        | ImageError::TrimOutOfRange{..}
+        | ImageError::LengthOutOfRange{..}
+        | ImageError::CropOutOfRange{..}
+        | ImageError::Strict(_) => EXIT_BAD_LAYOUT
+    }
+}
 
-            surface.update_window().unwrap();
+// exits with a code that depends on what went wrong
+fn fail(context: impl Display, err: ImageError) -> !
+{
+    let code = exit_code(&err);
 
-            thread::sleep(Duration::from_millis(1000 / 60));
-        }
-    }
+    complain_with(format!("{context}: {err}"), code)
 }
 
-struct Image
+fn major_order(config: &Config) -> MajorOrder
 {
-    data: Vec<Color>,
-    width: usize,
-    height: usize
+    if config.column_major { MajorOrder::Column } else { MajorOrder::Row }
 }
 
-impl Image
+fn raw_layout(path: &Path, config: &Config) -> Result<Layout, ImageError>
 {
-    pub fn parse(
-        path: impl AsRef<Path>,
-        width: usize,
-        c: Color,
-        trim_start: usize,
-        trim_end: usize
-    ) -> Self
-    {
-        let values = fs::read(path).unwrap();
+    let palette = config.palette.as_ref().map(load_palette).transpose()?;
+
+    let (width, height) = input_size(path, config);
+
+    Ok(Layout{
+        offset: config.pixel_offset,
+        width,
+        height,
+        aspect: config.aspect,
+        format: config.format,
+        depth: config.depth,
+        palette,
+        order: major_order(config)
+    })
+}
 
-        let bpp = 3;
-        let mut data: Vec<Color> = values[trim_start..(values.len() - trim_end)]
-            .chunks(bpp).map(|chunk|
-            {
-                let r = chunk[0];
-                let g = chunk.get(1).copied().unwrap_or(c.g);
-                let b = chunk.get(2).copied().unwrap_or(c.b);
+fn input_paths<'a>(path: &'a Path, config: &'a Config) -> Vec<&'a Path>
+{
+    iter::once(path).chain(config.append.iter().map(Path::new)).collect()
+}
 
-                Color::RGB(r, g, b)
-            }).collect();
+// bytes of a single pixel in the raw input
+fn pixel_bytes(config: &Config) -> usize
+{
+    // palettes have a single index byte per pixel
+    if config.palette.is_some() { 1 } else { config.format.bpp() * config.depth.bytes() }
+}
 
-        // ceil integer div
-        let height = {
-            let l = data.len();
-            let mut value = l / width;
+// splits the input into frames which get read from the file only when shown
+fn frames(path: &Path, config: &Config) -> Result<Frames, ImageError>
+{
+    let layout = raw_layout(path, config)?;
 
-            if l % width != 0
-            {
-                value += 1;
-            }
+    let (Some(width), Some(height)) = (layout.width, layout.height) else
+    {
+        complain("--frames needs the --width and --height of a single frame");
+    };
 
-            value
-        };
+    let frame_bytes = width * height * pixel_bytes(config);
 
-        let total = width * height;
-        if total < data.len()
-        {
-            panic!("total should never be less than len so far");
-        }
+    let paths = input_paths(path, config).into_iter().map(PathBuf::from).collect::<Vec<_>>();
 
-        eprintln!("total amount of pixels: {}, total amount of bytes: {}", total, total * bpp);
+    let total = paths.iter().map(|path| fs::metadata(path).map(|x| x.len() as usize)).sum::<io::Result<usize>>()?;
 
-        data.resize(total, c);
+    let start = config.pixel_offset + config.trim_start;
+    let available = config.length.unwrap_or_else(|| total.saturating_sub(start + config.trim_end));
 
-        Self{
-            data,
-            width,
-            height
-        }
+    let count = available / frame_bytes;
+    if count == 0
+    {
+        return Err(ImageError::BadDimensions(
+            format!("a {width}x{height} frame needs {frame_bytes} bytes, only {available} are left after trimming")
+        ));
     }
 
-    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()>
+    let leftover = available % frame_bytes;
+    if leftover != 0
     {
-        let s = self.data.iter().flat_map(|c|
+        let message = format!("the last {leftover} bytes dont make a whole frame");
+        if config.strictness() >= Strictness::Strict
         {
-            [c.r, c.g, c.b]
-        }).collect::<Vec<u8>>();
+            return Err(ImageError::Strict(message));
+        }
 
-        fs::write(path, s)
+        config.verbosity.info(format!("warning: {message}, skipping them"));
     }
 
-    pub fn unhilbertify(&mut self)
-    {
-        assert_eq!(self.width, self.height);
+    config.verbosity.info(format!("playing {count} frames of {width}x{height}"));
 
-        let size = self.width;
-        let curve = HilbertCurve::new(size);
+    let config = config.clone();
 
-        self.remap_positions(|index|
+    Ok(Frames{
+        count,
+        load: Box::new(move |index|
         {
-            let pos = curve.value_to_point(index);
+            let mut image = Image::parse_many(
+                &paths,
+                layout,
+                config.background,
+                config.trim_start + index * frame_bytes,
+                DataEnd::Length(frame_bytes),
+                config.strictness(),
+                Verbosity::Quiet
+            )?;
+
+            transform(&mut image, &config)?;
+
+            Ok(image)
+        })
+    })
+}
 
-            Self::to_index_assoc(size, pos)
-        });
+// the given size, or the one in the file name if asked for
+fn input_size(path: &Path, config: &Config) -> (Option<usize>, Option<usize>)
+{
+    if config.width.is_some() || !config.dims_from_name
+    {
+        return (config.width, config.height);
     }
 
-    pub fn hilbertify(&mut self)
+    match size_from_name(path)
     {
-        assert_eq!(self.width, self.height);
-
-        let size = self.width;
-        let curve = HilbertCurve::new(size);
+        Some(size) =>
+        {
+            config.verbosity.verbose(format!("took {}x{} from the file name", size.x, size.y));
 
-        self.remap_positions(|index|
+            (Some(size.x), Some(config.height.unwrap_or(size.y)))
+        },
+        None =>
         {
-            let pos = Self::index_to_pos_assoc(size, index);
+            config.verbosity.info(format!("no WIDTHxHEIGHT in the name of {}, inferring the size", path.display()));
 
-            curve.point_to_value(pos)
-        });
+            (None, config.height)
+        }
     }
+}
 
-    fn remap_positions(&mut self, mut f: impl FnMut(usize) -> usize)
-    {
-        let mut output = self.data.clone();
+// parses a single file and applies every configured transform except the final hilbertify
+fn process_one(path: impl AsRef<Path>, config: &Config) -> Result<Image, ImageError>
+{
+    let mut image = load(path, config)?;
 
-        self.data.iter().enumerate().for_each(|(i, value)|
-        {
-            let new_position = f(i);
+    transform(&mut image, config)?;
 
-            output[new_position] = *value;
-        });
+    Ok(image)
+}
 
-        self.data = output;
-    }
+fn transform(image: &mut Image, config: &Config) -> Result<(), ImageError>
+{
+    image.channel = config.channel;
 
-    pub fn to_index(&self, pos: Pos2<usize>) -> usize
+    if config.keep_depth
     {
-        Self::to_index_assoc(self.width, pos)
+        image.save_depth = config.depth;
     }
 
-    pub fn to_index_assoc(width: usize, pos: Pos2<usize>) -> usize
-    {
-        pos.y * width + pos.x
-    }
+    config.enabled_steps().into_iter().try_for_each(|step| apply_step(image, step, config))
+}
 
-    pub fn index_to_pos_assoc(width: usize, index: usize) -> Pos2<usize>
+#[allow(clippy::suspicious_else_formatting)]
+fn apply_step(image: &mut Image, step: Step, config: &Config) -> Result<(), ImageError>
+{
+    match step
     {
-        Pos2{
-            x: index % width,
-            y: index / width
+        Step::Crop =>
+        {
+            if let Some(area) = config.crop
+            {
+                image.crop(area.pos.x, area.pos.y, area.size.x, area.size.y)?;
+            }
+        },
+        Step::Resize =>
+        {
+            if let Some(size) = config.resize
+            {
+                image.resize(size.x, size.y);
+            }
+        },
+        Step::AutoSquare =>
+        {
+            let size = image.crop_power_of_two_square()?;
+
+            config.verbosity.info(format!("cropped to a {size}x{size} square"));
+        },
+        Step::Unhilbertify =>
+        {
+            if config.channel_curves.is_empty()
+            {
+                image.unhilbertify(config.curve)?;
+            } else
+            {
+                image.unhilbertify_channels(&config.channel_curves)?;
+            }
+        },
+        Step::Rotate => image.rotate90(config.rotate),
+        Step::FlipH => image.flip_horizontal(),
+        Step::FlipV => image.flip_vertical(),
+        Step::ChannelOrder =>
+        {
+            if let Some(order) = config.channel_order
+            {
+                image.swap_channels(order.0);
+            }
+        },
+        Step::Invert => image.invert(),
+        Step::Quantize =>
+        {
+            if let Some(levels) = config.quantize
+            {
+                image.quantize(levels);
+            }
+        },
+        Step::Gamma =>
+        {
+            // only meant for viewing unless asked for
+            if let Some(gamma) = config.gamma.filter(|_| !config.saving() || config.gamma_save)
+            {
+                image.apply_gamma(gamma);
+            }
+        },
+        Step::ApplyMap =>
+        {
+            if let Some(path) = &config.apply_map
+            {
+                let map = load_map(path)?;
+
+                image.apply_map(&map)?;
+            }
         }
     }
+
+    Ok(())
 }
 
-impl Index<Pos2<usize>> for Image
+// the only place that touches sdl, everything else has to work on a headless machine
+fn show(images: Vec<Image>, frames: Option<Frames>, config: &Config)
 {
-    type Output = Color;
+    DrawerWindow::new(images, WindowSettings{frames, ..window_settings(config)}).wait_exit();
+}
 
-    fn index(&self, index: Pos2<usize>) -> &Self::Output
-    {
-        &self.data[self.to_index(index)]
+fn window_settings(config: &Config) -> WindowSettings
+{
+    WindowSettings{
+        background: config.background,
+        scale: config.scale,
+        pixel_aspect: config.pixel_aspect.map_or(Pos2{x: 1, y: 1}, |aspect| aspect.0),
+        curve: config.curve,
+        fit: config.fit,
+        canvas: config.canvas,
+        preview_max: config.preview_max,
+        grid: config.grid,
+        checker: config.checker,
+        grid_color: config.grid_color,
+        animate: config.animate,
+        fps: config.fps,
+        data_start: config.pixel_offset + config.trim_start,
+        pixel_bytes: pixel_bytes(config),
+        frames: None,
+        verbosity: config.verbosity
     }
 }
 
-impl IndexMut<Pos2<usize>> for Image
+// describes what processing the input would do without writing anything
+#[allow(clippy::suspicious_else_formatting)]
+fn dry_run(path: &Path, save_path: Option<&Path>, config: &Config) -> Result<(), ImageError>
 {
-    fn index_mut(&mut self, index: Pos2<usize>) -> &mut Self::Output
+    let mut image = load(path, config)?;
+
+    println!(
+        "{}: {}x{} with {} bytes per pixel, {} padding pixels",
+        path.display(),
+        image.width,
+        image.height,
+        image.format.bpp(),
+        image.padding
+    );
+
+    let steps = config.enabled_steps().iter().map(|step| step.to_string()).collect::<Vec<_>>();
+
+    if steps.is_empty()
+    {
+        println!("transforms: none");
+    } else
+    {
+        transform(&mut image, config)?;
+
+        println!("transforms: {} (then {}x{})", steps.join(" -> "), image.width, image.height);
+    }
+
+    match save_path
     {
-        let index = self.to_index(index);
+        Some(save_path) =>
+        {
+            println!("would save to {} remapped with {}", save_path.display(), config.save_remap());
 
-        &mut self.data[index]
+            if config.meta
+            {
+                println!("would describe it in {}", meta_path(save_path).display());
+            }
+        },
+        None => println!("would open a window")
     }
+
+    Ok(())
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Pos2<T>
+// remaps and saves, writing the .meta sidecar if asked for
+fn save_output(mut image: Image, remap: &Remap, save_path: &Path, config: &Config) -> Result<(), ImageError>
 {
-    x: T,
-    y: T
+    image.remap(remap)?;
+    image.save(save_path)?;
+
+    if config.meta
+    {
+        fs::write(meta_path(save_path), image.meta(remap, &config.enabled_steps()))?;
+    }
+
+    Ok(())
+}
+
+// paths that dont exist yet cant be the same as an existing one
+fn same_path(a: &Path, b: &Path) -> bool
+{
+    fs::canonicalize(a).ok()
+        .zip(fs::canonicalize(b).ok())
+        .is_some_and(|(a, b)| a == b)
 }
 
-struct HilbertCurve
+fn check_overwrite(input: &Path, output: &Path, config: &Config)
 {
-    order: usize
+    if !config.force && same_path(input, output)
+    {
+        complain(format!("refusing to overwrite the input {}, use --force if thats intended", input.display()));
+    }
 }
 
-impl HilbertCurve
+// saves every file in the input directory into the output directory under the same name
+#[allow(clippy::suspicious_else_formatting)]
+fn process_directory(input: &Path, config: &Config)
 {
-    pub fn new(size: usize) -> Self
+    let output = config.save_path.as_ref().or(config.outdir.as_ref()).map(Path::new).unwrap_or_else(||
+    {
+        complain("a directory input needs --save or --outdir to point at an output directory")
+    });
+
+    if same_path(input, output)
     {
-        let mut order = 0;
+        complain("the output directory cant be the same as the input directory");
+    }
 
-        let mut current = size;
-        while current > 0
+    if !config.dry_run
+    {
+        fs::create_dir_all(output).unwrap_or_else(|err|
         {
-            current /= 2;
+            complain_with(format!("cant create {}: {err}", output.display()), EXIT_IO)
+        });
+    }
 
-            order += 1;
-        }
+    let mut paths = fs::read_dir(input).and_then(|entries|
+    {
+        entries.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()
+    }).unwrap_or_else(|err|
+    {
+        complain_with(format!("cant read {}: {err}", input.display()), EXIT_IO)
+    });
 
-        order -= 1;
+    paths.retain(|path| path.is_file());
+    paths.sort();
 
-        if current != 0
+    let failed = paths.iter().filter(|path|
+    {
+        let save_path = if config.outdir.is_some()
         {
-            panic!("size must be a power of 2");
-        }
+            output.join(config.auto_name(path))
+        } else
+        {
+            output.join(path.file_name().unwrap())
+        };
 
-        Self{order}
-    }
+        let result = if config.dry_run
+        {
+            dry_run(path, Some(&save_path), config)
+        } else
+        {
+            process_one(path, config).and_then(|image| save_output(image, &config.save_remap(), &save_path, config))
+        };
 
-    fn rotate(&self, mut pos: Pos2<usize>, check: Pos2<usize>, value: usize) -> Pos2<usize>
-    {
-        if check.y != 0
+        match result
         {
-            return pos;
+            Ok(()) =>
+            {
+                // a dry run only reports what would happen
+                let action = if config.dry_run { "would save" } else { "saved" };
+
+                config.verbosity.verbose(format!("{action} {} to {}", path.display(), save_path.display()));
+
+                false
+            },
+            Err(err) =>
+            {
+                eprintln!("skipping {}: {err}", path.display());
+
+                true
+            }
         }
+    }).count();
 
-        if check.x == 1
+    config.verbosity.info(format!("processed {} files, skipped {failed}", paths.len() - failed));
+}
+
+fn main()
+{
+    let config = Config::parse(env::args().skip(1));
+
+    if let Some(size) = config.generate
+    {
+        let image = Image::generate(config.curve, size.x, size.y, config.format, config.background)
+            .and_then(|mut image| transform(&mut image, &config).map(|_| image))
+            .unwrap_or_else(|err| fail("could not generate the pattern", err));
+
+        // saved as is, remapping would put the pattern back into raster order
+        if let Some(save_path) = &config.save_path
         {
-            pos.x = value - 1 - pos.x;
-            pos.y = value - 1 - pos.y;
+            save_output(image, &Remap::None, Path::new(save_path), &config).unwrap_or_else(|err|
+            {
+                fail(format!("could not save to {save_path}"), err)
+            });
+            return;
         }
 
-        Pos2{x: pos.y, y: pos.x}
+        show(vec![image], None, &config);
+        return;
     }
 
-    #[allow(dead_code)]
-    pub fn point_to_value(&self, mut pos: Pos2<usize>) -> usize
-    {
-        let n = 2_usize.pow(self.order as u32);
+    let input = Path::new(&config.input);
 
-        (0..self.order).rev().map(|s|
+    if let Some(amount) = config.inspect
+    {
+        let dump = Image::hex_dump(input, amount).unwrap_or_else(|err|
         {
-            let s = 2_usize.pow(s as u32);
-
-            let rx = ((pos.x & s) > 0) as usize;
-            let ry = ((pos.y & s) > 0) as usize;
+            fail(format!("could not read {}", config.input), err)
+        });
 
-            pos = self.rotate(pos, Pos2{x: rx, y: ry}, n);
+        print!("{dump}");
 
-            s * s * ((3 * rx) ^ ry)
-        }).sum()
+        return;
     }
 
-    pub fn value_to_point(&self, mut value: usize) -> Pos2<usize>
+    if config.stats
     {
-        let mut pos = Pos2{x: 0, y: 0};
-
-        for s in 0..self.order
+        let image = load(input, &config).unwrap_or_else(|err|
         {
-            let s = 2_usize.pow(s as u32);
-
-            let rx = (value / 2) & 1;
-            let ry = (value ^ rx) & 1;
+            fail(format!("could not load {}", config.input), err)
+        });
 
-            pos = self.rotate(pos, Pos2{x: rx, y: ry}, s);
+        image.stats().iter().for_each(|stats| eprintln!("{stats}"));
 
-            pos.x += s * rx;
-            pos.y += s * ry;
+        return;
+    }
 
-            value /= 4;
+    if input.is_dir()
+    {
+        if !config.append.is_empty() || config.frames
+        {
+            complain("--append and --frames cant be used with a directory input");
         }
 
-        pos
+        process_directory(input, &config);
+        return;
     }
-}
-
-fn resave(mut image: Image, config: Config)
-{
-    let save_path = config.save_path.unwrap();
 
-    image.hilbertify();
-
-    image.save(save_path).unwrap();
-}
+    if config.frames
+    {
+        let frames = frames(input, &config).unwrap_or_else(|err|
+        {
+            fail(format!("could not split {} into frames", config.input), err)
+        });
 
-fn main()
-{
-    let config = Config::parse(env::args().skip(1));
+        let first = (frames.load)(0).unwrap_or_else(|err|
+        {
+            fail(format!("could not load the first frame of {}", config.input), err)
+        });
 
-    let mut image = Image::parse(
-        &config.input,
-        config.width,
-        Color::RGB(0, 0, 0),
-        config.trim_start,
-        config.trim_end
-    );
+        show(vec![first], Some(frames), &config);
 
-    if config.unhilbertify
-    {
-        image.unhilbertify();
+        return;
     }
 
-    if config.save_path.is_some()
+    if config.dry_run
     {
-        resave(image, config);
+        dry_run(input, config.output_path(input).as_deref(), &config).unwrap_or_else(|err|
+        {
+            fail(format!("could not process {}", config.input), err)
+        });
+
         return;
     }
 
-    let window = DrawerWindow::new(image);
+    let image = process_one(input, &config).unwrap_or_else(|err|
+    {
+        fail(format!("could not process {}", config.input), err)
+    });
 
-    window.wait_exit();
-}
+    if let Some(path) = &config.dump_map
+    {
+        check_overwrite(input, Path::new(path), &config);
 
-#[cfg(test)]
-mod tests
-{
-    use super::*;
+        image.permutation(config.curve).and_then(|map| save_map(&map, path)).unwrap_or_else(|err|
+        {
+            fail(format!("could not dump the map to {path}"), err)
+        });
+    }
 
-    #[test]
-    fn inverse_hilbert()
+    if let Some(save_path) = config.output_path(input)
     {
-        let n = 512;
+        if let Some(outdir) = &config.outdir
+        {
+            fs::create_dir_all(outdir).unwrap_or_else(|err| complain_with(format!("cant create {outdir}: {err}"), EXIT_IO));
+        }
 
-        let curve = HilbertCurve::new(n);
+        check_overwrite(input, &save_path, &config);
 
-        let total = n * n;
-        for i in 0..total
+        save_output(image, &config.save_remap(), &save_path, &config).unwrap_or_else(|err|
         {
-            let point = curve.value_to_point(i);
+            fail(format!("could not save to {}", save_path.display()), err)
+        });
 
-            assert_eq!(curve.point_to_value(point), i);
-        }
+        config.verbosity.verbose(format!("saved to {}", save_path.display()));
+
+        return;
+    }
+
+    let mut images = vec![image];
+
+    if let Some(path) = &config.compare
+    {
+        let other = process_one(path, &config).unwrap_or_else(|err|
+        {
+            fail(format!("could not process {path}"), err)
+        });
+
+        images.push(other);
     }
+
+    show(images, None, &config);
 }
@@ -0,0 +1,1168 @@
+use std::{
+    io,
+    ops::Range,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
+
+use sdl2::{
+    EventPump,
+    rect::Rect,
+    pixels::{Color, PixelFormatEnum},
+    event::{Event, WindowEvent},
+    keyboard::Keycode,
+    video::{Window, FullscreenType},
+    surface::SurfaceRef
+};
+
+use imagedisplay::{
+    Pos2,
+    Verbosity,
+    image::{Image, ImageError, PixelFormat},
+    config::CurveType
+};
+
+
+fn composite(top: Color, bottom: Color) -> Color
+{
+    let a = top.a as u32;
+    let mix = |t: u8, b: u8|
+    {
+        ((t as u32 * a + b as u32 * (255 - a)) / 255) as u8
+    };
+
+    Color::RGB(mix(top.r, bottom.r), mix(top.g, bottom.g), mix(top.b, bottom.b))
+}
+
+fn inverted(color: Color) -> Color
+{
+    Color::RGB(!color.r, !color.g, !color.b)
+}
+
+// splits an area into the checkerboard squares it covers, true for the light squares,
+// positions are relative to where the checkerboard starts
+fn checker_cells(pos: Pos2<i32>, size: Pos2<i32>, checker: i32) -> Vec<(Pos2<i32>, Pos2<i32>, bool)>
+{
+    let splits = |start: i32, length: i32|
+    {
+        let mut splits = Vec::new();
+
+        let mut current = start;
+        while current < start + length
+        {
+            let cell = current.div_euclid(checker);
+            let next = ((cell + 1) * checker).min(start + length);
+
+            splits.push((current, next - current, cell));
+
+            current = next;
+        }
+
+        splits
+    };
+
+    let columns = splits(pos.x, size.x);
+
+    splits(pos.y, size.y).into_iter().flat_map(|(y, height, cell_y)|
+    {
+        columns.iter().map(move |(x, width, cell_x)|
+        {
+            (Pos2{x: *x, y}, Pos2{x: *width, y: height}, (cell_x + cell_y).rem_euclid(2) == 0)
+        })
+    }).collect()
+}
+
+// whats behind transparent pixels
+#[derive(Clone, Copy)]
+enum Backdrop
+{
+    Solid(Color),
+    // squares this many window pixels big
+    Checker(usize)
+}
+
+impl Backdrop
+{
+    const LIGHT: Color = Color::RGB(204, 204, 204);
+    const DARK: Color = Color::RGB(153, 153, 153);
+
+    // origin is where the checkerboard starts so it moves along with the image
+    fn fill(&self, surface: &mut SurfaceRef, rect: Rect, pixel: Color, origin: Pos2<i32>)
+    {
+        match self
+        {
+            Self::Checker(checker) if pixel.a != 255 =>
+            {
+                let pos = Pos2{x: rect.x() - origin.x, y: rect.y() - origin.y};
+                let size = Pos2{x: rect.width() as i32, y: rect.height() as i32};
+
+                checker_cells(pos, size, *checker as i32).into_iter().for_each(|(pos, size, light)|
+                {
+                    let rect = Rect::new(origin.x + pos.x, origin.y + pos.y, size.x as u32, size.y as u32);
+                    let shade = if light { Self::LIGHT } else { Self::DARK };
+
+                    surface.fill_rect(rect, composite(pixel, shade)).unwrap();
+                });
+            },
+            Self::Checker(_) => surface.fill_rect(rect, pixel).unwrap(),
+            Self::Solid(background) => surface.fill_rect(rect, composite(pixel, *background)).unwrap()
+        }
+    }
+}
+
+fn stretched(size: Pos2<usize>, aspect: Pos2<usize>) -> Pos2<usize>
+{
+    Pos2{x: size.x * aspect.x, y: size.y * aspect.y}
+}
+
+pub struct WindowSettings
+{
+    pub background: Color,
+    pub scale: usize,
+    // drawn size of a single image pixel before scaling, 2x1 for pixels twice as wide as theyre tall
+    pub pixel_aspect: Pos2<usize>,
+    pub curve: CurveType,
+    pub fit: bool,
+    pub canvas: Option<Pos2<usize>>,
+    // longest side of the downscaled copies that get shown instead of the images
+    pub preview_max: Option<usize>,
+    pub grid: bool,
+    // size of the checkerboard squares shown behind transparent pixels, the background otherwise
+    pub checker: Option<usize>,
+    // faint version of the inverted background if missing
+    pub grid_color: Option<Color>,
+    // how long toggling the curve animates for
+    pub animate: Option<Duration>,
+    // how often animations get redrawn
+    pub fps: usize,
+    // where the pixels start in the input and how many bytes each one takes, for the inspector
+    pub data_start: usize,
+    pub pixel_bytes: usize,
+    pub frames: Option<Frames>,
+    pub verbosity: Verbosity
+}
+
+// frames of a dump played one after another
+pub struct Frames
+{
+    pub count: usize,
+    // decodes the frame at this index
+    pub load: Box<dyn Fn(usize) -> Result<Image, ImageError>>
+}
+
+struct Animation
+{
+    start: Instant,
+    // raster index every pixel is moving from, indexed by where it ends up, one list per image
+    sources: Vec<Vec<usize>>
+}
+
+pub struct DrawerWindow
+{
+    window: Window,
+    events: EventPump,
+    // drawn side by side from left to right
+    images: Vec<Image>,
+    // shown instead of the full size images if set, saving and remapping still use the full ones
+    previews: Option<Vec<Image>>,
+    preview_max: Option<usize>,
+    background: Color,
+    backdrop: Backdrop,
+    grid: bool,
+    grid_color: Color,
+    scale: usize,
+    pixel_aspect: Pos2<usize>,
+    // amount of image pixels per drawn pixel, only above 1 when fitting a big image
+    shrink: usize,
+    zoom: usize,
+    curve: CurveType,
+    hilbertified: bool,
+    animate: Option<Duration>,
+    animation: Option<Animation>,
+    frames: Option<Frames>,
+    frame: usize,
+    paused: bool,
+    frame_start: Instant,
+    frame_time: Duration,
+    // a press that hasnt turned into a drag yet
+    clicking: bool,
+    data_start: usize,
+    pixel_bytes: usize,
+    verbosity: Verbosity,
+    // position of the images top left corner in the window
+    offset: Pos2<i32>
+}
+
+impl DrawerWindow
+{
+    const MAX_ZOOM: usize = 32;
+
+    // width of the line between images in drawn pixels
+    const DIVIDER: usize = 1;
+
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn new(images: Vec<Image>, settings: WindowSettings) -> Self
+    {
+        let WindowSettings{
+            background,
+            scale,
+            pixel_aspect,
+            curve,
+            fit,
+            canvas,
+            preview_max,
+            grid,
+            checker,
+            grid_color,
+            animate,
+            fps,
+            data_start,
+            pixel_bytes,
+            frames,
+            verbosity
+        } = settings;
+
+        let grid_color = grid_color.unwrap_or_else(||
+        {
+            let color = inverted(background);
+
+            composite(Color::RGBA(color.r, color.g, color.b, 64), background)
+        });
+
+        let previews = Self::make_previews(&images, preview_max);
+        let shown = previews.as_deref().unwrap_or(&images);
+
+        let ctx = sdl2::init().unwrap();
+
+        let video = ctx.video().unwrap();
+
+        let (scale, shrink) = if fit
+        {
+            let bounds = video.display_bounds(0).unwrap();
+
+            let display = Pos2{x: bounds.width() as usize, y: bounds.height() as usize};
+
+            Self::fit_scale(stretched(Self::layout_size(shown, 1), pixel_aspect), scale, display)
+        } else
+        {
+            (scale, 1)
+        };
+
+        if shrink > 1
+        {
+            verbosity.info(format!("scaled down by {shrink} to fit the display"));
+        }
+
+        let window_size = canvas.unwrap_or_else(|| stretched(Self::layout_size(shown, shrink), pixel_aspect) * scale);
+
+        let (window_width, window_height) = (window_size.x as u32, window_size.y as u32);
+
+        let window = video.window(&Self::title(&images, curve, frames.as_ref().map(|frames| (0, frames.count))), window_width, window_height)
+            .resizable()
+            .build()
+            .unwrap();
+
+        let events = ctx.event_pump().unwrap();
+
+        let mut this = Self{
+            window,
+            events,
+            images,
+            previews,
+            preview_max,
+            background,
+            backdrop: checker.map_or(Backdrop::Solid(background), Backdrop::Checker),
+            grid,
+            grid_color,
+            scale,
+            pixel_aspect,
+            shrink,
+            zoom: 1,
+            curve,
+            hilbertified: false,
+            animate,
+            animation: None,
+            frames,
+            frame: 0,
+            paused: false,
+            frame_start: Instant::now(),
+            frame_time: Duration::from_secs(1) / fps as u32,
+            clicking: false,
+            data_start,
+            pixel_bytes,
+            verbosity,
+            offset: Pos2{x: 0, y: 0}
+        };
+
+        this.center();
+        this.draw();
+
+        this
+    }
+
+    fn make_previews(images: &[Image], preview_max: Option<usize>) -> Option<Vec<Image>>
+    {
+        preview_max.map(|max| images.iter().map(|image| image.downscaled(max)).collect())
+    }
+
+    // images that get drawn
+    fn shown(&self) -> &[Image]
+    {
+        self.previews.as_deref().unwrap_or(&self.images)
+    }
+
+    // returns the scale and the shrink amount needed to fit inside the display
+    fn fit_scale(size: Pos2<usize>, scale: usize, display: Pos2<usize>) -> (usize, usize)
+    {
+        if size.x * scale <= display.x && size.y * scale <= display.y
+        {
+            return (scale, 1);
+        }
+
+        Self::largest_fit(size, display)
+    }
+
+    // biggest scale (or smallest shrink) that still shows the whole image
+    fn largest_fit(size: Pos2<usize>, display: Pos2<usize>) -> (usize, usize)
+    {
+        let scale = (display.x / size.x).min(display.y / size.y);
+        if scale > 0
+        {
+            return (scale, 1);
+        }
+
+        (1, size.x.div_ceil(display.x).max(size.y.div_ceil(display.y)))
+    }
+
+    // size of all the images next to each other with dividers between them in drawn pixels
+    fn layout_size(images: &[Image], shrink: usize) -> Pos2<usize>
+    {
+        let widths: usize = images.iter().map(|image| image.width.div_ceil(shrink)).sum();
+        let dividers = images.len().saturating_sub(1) * Self::DIVIDER;
+
+        Pos2{
+            x: widths + dividers,
+            y: images.iter().map(|image| image.height.div_ceil(shrink)).max().unwrap_or(0)
+        }
+    }
+
+    fn resized(&mut self, width: i32, height: i32)
+    {
+        if width <= 0 || height <= 0
+        {
+            return;
+        }
+
+        (self.scale, self.shrink) = Self::largest_fit(
+            stretched(Self::layout_size(self.shown(), 1), self.pixel_aspect),
+            Pos2{x: width as usize, y: height as usize}
+        );
+
+        self.zoom = 1;
+
+        self.center();
+        self.draw();
+    }
+
+    fn center(&mut self)
+    {
+        let size = self.pixel_size();
+        let view = self.view_size();
+        let (window_width, window_height) = self.window.size();
+
+        self.offset = Pos2{
+            x: (window_width as i32 - (view.x * size.x) as i32) / 2,
+            y: (window_height as i32 - (view.y * size.y) as i32) / 2
+        };
+
+        self.clamp_offset();
+    }
+
+    // frame is the shown index and the frame count
+    fn title(images: &[Image], curve: CurveType, frame: Option<(usize, usize)>) -> String
+    {
+        let sizes = images.iter().map(|image|
+        {
+            let pixels = image.width * image.height;
+
+            format!(
+                "{}x{} ({pixels} px, {} bytes)",
+                image.width,
+                image.height,
+                pixels * image.format.bpp()
+            )
+        }).collect::<Vec<_>>();
+
+        let title = format!("{} - {curve}", sizes.join(" | "));
+
+        match frame
+        {
+            Some((index, count)) => format!("{title} - frame {}/{count}", index + 1),
+            None => title
+        }
+    }
+
+    fn update_title(&mut self)
+    {
+        let frame = self.frames.as_ref().map(|frames| (self.frame, frames.count));
+
+        let title = Self::title(&self.images, self.curve, frame);
+        self.window.set_title(&title).unwrap();
+    }
+
+    // drawn width and height of a single pixel
+    fn pixel_size(&self) -> Pos2<usize>
+    {
+        self.pixel_aspect * (self.scale * self.zoom)
+    }
+
+    // size of the images in drawn pixels
+    fn view_size(&self) -> Pos2<usize>
+    {
+        Self::layout_size(self.shown(), self.shrink)
+    }
+
+    fn draw(&mut self)
+    {
+        let mut surface = self.window.surface(&self.events).unwrap();
+
+        surface.fill_rect(None, self.background).unwrap();
+
+        let size = self.pixel_size();
+        let height = (self.view_size().y * size.y) as u32;
+
+        let divider = inverted(self.background);
+
+        let mut offset = self.offset;
+        for (index, image) in self.shown().iter().enumerate()
+        {
+            if index > 0
+            {
+                let width = (Self::DIVIDER * size.x) as u32;
+                surface.fill_rect(Rect::new(offset.x, offset.y, width, height), divider).unwrap();
+
+                offset.x += width as i32;
+            }
+
+            match &self.animation
+            {
+                Some(animation) =>
+                {
+                    let progress = animation.start.elapsed().as_secs_f32()
+                        / self.animate.map(|x| x.as_secs_f32()).unwrap_or(1.0);
+
+                    let sources = &animation.sources[index];
+
+                    self.draw_moving(&mut surface, image, sources, progress.min(1.0), offset);
+                },
+                None => Self::draw_image(&mut surface, image, offset, size, self.shrink, self.backdrop)
+            }
+
+            if self.grid
+            {
+                self.draw_grid(&mut surface, image, offset);
+            }
+
+            offset.x += (image.width.div_ceil(self.shrink) * size.x) as i32;
+        }
+
+        surface.update_window().unwrap();
+    }
+
+    // drawn pixels that end up inside the window
+    fn visible(offset: i32, window_size: u32, image_size: usize, size: usize) -> Range<usize>
+    {
+        let size = size as i32;
+
+        let start = (-offset).max(0) / size;
+        let end = (window_size as i32 - offset + size - 1) / size;
+
+        (start as usize).min(image_size)..(end.max(0) as usize).min(image_size)
+    }
+
+    // lines between the visible pixels
+    fn draw_grid(&self, surface: &mut SurfaceRef, image: &Image, offset: Pos2<i32>)
+    {
+        let size = self.pixel_size();
+
+        let view = Pos2{x: image.width.div_ceil(self.shrink), y: image.height.div_ceil(self.shrink)};
+
+        let (width, height) = ((view.x * size.x) as u32, (view.y * size.y) as u32);
+
+        // theres no room between pixels that are 1 window pixel big
+        if size.x >= 2
+        {
+            Self::visible(offset.x, surface.width(), view.x, size.x).filter(|x| *x > 0).for_each(|x|
+            {
+                let rect = Rect::new(offset.x + (x * size.x) as i32, offset.y, 1, height);
+
+                surface.fill_rect(rect, self.grid_color).unwrap();
+            });
+        }
+
+        if size.y >= 2
+        {
+            Self::visible(offset.y, surface.height(), view.y, size.y).filter(|y| *y > 0).for_each(|y|
+            {
+                let rect = Rect::new(offset.x, offset.y + (y * size.y) as i32, width, 1);
+
+                surface.fill_rect(rect, self.grid_color).unwrap();
+            });
+        }
+    }
+
+    fn draw_image(
+        surface: &mut SurfaceRef,
+        image: &Image,
+        offset: Pos2<i32>,
+        size: Pos2<usize>,
+        shrink: usize,
+        backdrop: Backdrop
+    )
+    {
+        let x_range = Self::visible(offset.x, surface.width(), image.width.div_ceil(shrink), size.x);
+        let y_range = Self::visible(offset.y, surface.height(), image.height.div_ceil(shrink), size.y);
+
+        for y in y_range
+        {
+            for x in x_range.clone()
+            {
+                let pixel = image[Pos2{x: x * shrink, y: y * shrink}];
+
+                let rect = Rect::new(
+                    offset.x + (x * size.x) as i32,
+                    offset.y + (y * size.y) as i32,
+                    size.x as u32,
+                    size.y as u32
+                );
+
+                // the surface has no alpha so transparent pixels get blended with the backdrop
+                backdrop.fill(surface, rect, pixel, offset);
+            }
+        }
+    }
+
+    // draws every pixel between where it was and where its going
+    fn draw_moving(
+        &self,
+        surface: &mut SurfaceRef,
+        image: &Image,
+        sources: &[usize],
+        progress: f32,
+        offset: Pos2<i32>
+    )
+    {
+        let (size, shrink) = (self.pixel_size(), self.shrink);
+
+        let lerp = |from: usize, to: usize|
+        {
+            (from as f32 + (to as f32 - from as f32) * progress) / shrink as f32
+        };
+
+        sources.iter().enumerate().for_each(|(index, source)|
+        {
+            let to = image.index_to_pos(index);
+
+            // same pixels as a still image shows
+            if !to.x.is_multiple_of(shrink) || !to.y.is_multiple_of(shrink)
+            {
+                return;
+            }
+
+            let from = image.index_to_pos(*source);
+
+            let rect = Rect::new(
+                offset.x + (lerp(from.x, to.x) * size.x as f32) as i32,
+                offset.y + (lerp(from.y, to.y) * size.y as f32) as i32,
+                size.x as u32,
+                size.y as u32
+            );
+
+            self.backdrop.fill(surface, rect, image[to], offset);
+        });
+    }
+
+    // keeps the image pixel under the cursor in place
+    fn zoom_at(&mut self, zoom: usize, cursor: Pos2<i32>)
+    {
+        let zoom = zoom.clamp(1, Self::MAX_ZOOM);
+        if zoom == self.zoom
+        {
+            return;
+        }
+
+        let old_size = self.pixel_size();
+
+        self.zoom = zoom;
+
+        let new_size = self.pixel_size();
+
+        let zoomed = |cursor: i32, offset: i32, old_size: usize, new_size: usize|
+        {
+            let cursor = cursor as i64;
+
+            (cursor - (cursor - offset as i64) * new_size as i64 / old_size as i64) as i32
+        };
+
+        self.offset = Pos2{
+            x: zoomed(cursor.x, self.offset.x, old_size.x, new_size.x),
+            y: zoomed(cursor.y, self.offset.y, old_size.y, new_size.y)
+        };
+
+        self.clamp_offset();
+
+        self.draw();
+    }
+
+    fn pan(&mut self, amount: Pos2<i32>)
+    {
+        self.offset = self.offset + amount;
+
+        self.clamp_offset();
+
+        self.draw();
+    }
+
+    // at least one image pixel always stays inside the window
+    fn clamp_offset(&mut self)
+    {
+        let size = self.pixel_size();
+        let (window_width, window_height) = self.window.size();
+
+        let clamp = |offset: i32, image_size: usize, window_size: u32, size: usize|
+        {
+            let size = size as i32;
+
+            let low = size - image_size as i32 * size;
+            let high = window_size as i32 - size;
+
+            offset.clamp(low.min(high), high.max(low))
+        };
+
+        let view = self.view_size();
+
+        self.offset = Pos2{
+            x: clamp(self.offset.x, view.x, window_width, size.x),
+            y: clamp(self.offset.y, view.y, window_height, size.y)
+        };
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn toggle_fullscreen(&mut self)
+    {
+        let mode = if self.window.fullscreen_state() == FullscreenType::Off
+        {
+            FullscreenType::Desktop
+        } else
+        {
+            FullscreenType::Off
+        };
+
+        if let Err(err) = self.window.set_fullscreen(mode)
+        {
+            eprintln!("cant change the fullscreen mode: {err}");
+            return;
+        }
+
+        // the resize event can arrive late so the new size gets fitted right away
+        let (width, height) = self.window.size();
+        self.resized(width as i32, height as i32);
+    }
+
+    // image index and its drawn column for a drawn column of the whole window, none on dividers
+    fn locate(images: &[Image], shrink: usize, column: usize) -> Option<(usize, usize)>
+    {
+        let mut column = column;
+        for (index, image) in images.iter().enumerate()
+        {
+            let width = image.width.div_ceil(shrink);
+            if column < width
+            {
+                return Some((index, column));
+            }
+
+            column = column.checked_sub(width + Self::DIVIDER)?;
+        }
+
+        None
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn inspect(&self, cursor: Pos2<i32>)
+    {
+        let size = self.pixel_size();
+
+        let Ok(relative) = Pos2::<usize>::try_from(cursor - self.offset) else
+        {
+            return;
+        };
+
+        let Some((index, column)) = Self::locate(self.shown(), self.shrink, relative.x / size.x) else
+        {
+            return;
+        };
+
+        let (image, shown) = (&self.images[index], &self.shown()[index]);
+
+        let pos = Pos2{x: column * self.shrink, y: relative.y / size.y * self.shrink};
+        if pos.y >= shown.height
+        {
+            return;
+        }
+
+        // same pixel the preview got sampled from
+        let pos = Pos2{x: pos.x * image.width / shown.width, y: pos.y * image.height / shown.height};
+
+        let pixel = image[pos];
+        let pixel_index = image.to_index(pos);
+
+        let prefix = if self.images.len() > 1 { format!("image {} ", index + 1) } else { String::new() };
+
+        // only lines up with the input before remapping, other transforms can still move pixels
+        let byte = if self.hilbertified
+        {
+            String::new()
+        } else
+        {
+            format!(", byte {}", self.data_start + pixel_index * self.pixel_bytes)
+        };
+
+        let alpha = if image.format.has_alpha() { format!(" a {}", pixel.a) } else { String::new() };
+
+        eprintln!(
+            "{prefix}pixel {},{} (index {pixel_index}{byte}): r {} g {} b {}{alpha}",
+            pos.x,
+            pos.y,
+            pixel.r,
+            pixel.g,
+            pixel.b
+        );
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn toggle_hilbert(&mut self)
+    {
+        if !self.hilbertified && self.curve.needs_square()
+        {
+            // checked upfront so the images dont end up in different orders
+            if let Some(image) = self.images.iter().find(|image| image.width != image.height)
+            {
+                eprintln!("cant hilbertify a {}x{} image, it must be square", image.width, image.height);
+                return;
+            }
+        }
+
+        let (curve, hilbertified) = (self.curve, self.hilbertified);
+        let sizes = self.images.iter().map(|image| (image.width, image.height)).collect::<Vec<_>>();
+
+        let result = self.images.iter_mut().try_for_each(|image|
+        {
+            if hilbertified
+            {
+                image.unhilbertify(curve)
+            } else
+            {
+                image.hilbertify(curve)
+            }
+        });
+
+        match result
+        {
+            Ok(()) =>
+            {
+                self.hilbertified = !self.hilbertified;
+
+                let same_size = self.images.iter().zip(&sizes)
+                    .all(|(image, size)| (image.width, image.height) == *size);
+
+                self.previews = Self::make_previews(&self.images, self.preview_max);
+
+                if self.animate.is_some()
+                {
+                    if self.previews.is_some()
+                    {
+                        self.verbosity.info("cant animate a downscaled preview");
+                    } else if same_size
+                    {
+                        self.start_animation();
+                    } else
+                    {
+                        self.verbosity.info("cant animate an image that got padded, it must be a power of 2 square");
+                    }
+                }
+
+                self.update_title();
+
+                self.clamp_offset();
+                self.draw();
+            },
+            Err(err) => eprintln!("error remapping the image: {err}")
+        }
+    }
+
+    fn start_animation(&mut self)
+    {
+        let sources = self.images.iter().map(|image|
+        {
+            Self::animation_sources(image, self.curve, self.hilbertified)
+        }).collect::<Result<Vec<_>, ImageError>>();
+
+        match sources
+        {
+            Ok(sources) => self.animation = Some(Animation{start: Instant::now(), sources}),
+            Err(err) => eprintln!("error animating the image: {err}")
+        }
+    }
+
+    // where every pixel of an already remapped image came from
+    #[allow(clippy::suspicious_else_formatting)]
+    fn animation_sources(image: &Image, curve: CurveType, hilbertified: bool) -> Result<Vec<usize>, ImageError>
+    {
+        let map = image.permutation(curve)?;
+
+        if hilbertified
+        {
+            let mut sources = vec![0; map.len()];
+            map.into_iter().enumerate().for_each(|(index, destination)| sources[destination] = index);
+
+            Ok(sources)
+        } else
+        {
+            Ok(map)
+        }
+    }
+
+    fn timestamp() -> u64
+    {
+        SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0)
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    fn save(&self)
+    {
+        let timestamp = Self::timestamp();
+
+        for (index, image) in self.images.iter().enumerate()
+        {
+            let path = if self.images.len() == 1
+            {
+                format!("imagedisplay_{timestamp}.raw")
+            } else
+            {
+                format!("imagedisplay_{timestamp}_{index}.raw")
+            };
+
+            match image.save(&path)
+            {
+                Ok(()) => self.verbosity.info(format!("saved the image to {path}")),
+                Err(err) => eprintln!("error saving to {path}: {err}")
+            }
+        }
+    }
+
+    // saves what the window shows right now, with the scaling and the grid
+    fn screenshot(&self)
+    {
+        let path = format!("imagedisplay_{}_screenshot.png", Self::timestamp());
+
+        let result = self.window.surface(&self.events)
+            .and_then(|surface| surface.convert_format(PixelFormatEnum::RGB24))
+            .map_err(|err| io::Error::other(err).into())
+            .and_then(|surface|
+            {
+                let (width, height) = (surface.width() as usize, surface.height() as usize);
+                let pitch = surface.pitch() as usize;
+
+                let mut image = Image::new(width, height, PixelFormat::Rgb, self.background);
+
+                // rows can have padding at the end
+                surface.with_lock(|bytes|
+                {
+                    image.data = bytes.chunks(pitch).take(height).flat_map(|row|
+                    {
+                        row[..width * 3].chunks(3).map(|pixel| Color::RGB(pixel[0], pixel[1], pixel[2]))
+                    }).collect();
+                });
+
+                image.save_png(&path)
+            });
+
+        match result
+        {
+            Ok(()) => self.verbosity.info(format!("saved a screenshot to {path}")),
+            Err(err) => eprintln!("error saving a screenshot to {path}: {err}")
+        }
+    }
+
+    // returns false when the window should close
+    fn handle_event(&mut self, event: Event) -> bool
+    {
+        match event
+        {
+            Event::Quit{..} => return false,
+            Event::MouseWheel{y, ..} if y != 0 =>
+            {
+                let mouse = self.events.mouse_state();
+                let cursor = Pos2{x: mouse.x(), y: mouse.y()};
+
+                let zoom = if y > 0 { self.zoom * 2 } else { self.zoom / 2 };
+
+                self.zoom_at(zoom, cursor);
+            },
+            Event::Window{win_event: WindowEvent::Resized(width, height), ..} =>
+            {
+                self.resized(width, height);
+            },
+            Event::Window{win_event: WindowEvent::Exposed, ..} => self.draw(),
+            Event::KeyDown{keycode: Some(Keycode::S), repeat: false, ..} => self.save(),
+            Event::KeyDown{keycode: Some(Keycode::P), repeat: false, ..} => self.screenshot(),
+            Event::KeyDown{keycode: Some(Keycode::F), repeat: false, ..} =>
+            {
+                self.toggle_fullscreen();
+            },
+            Event::KeyDown{keycode: Some(Keycode::G), repeat: false, ..} =>
+            {
+                self.grid = !self.grid;
+
+                self.draw();
+            },
+            Event::KeyDown{keycode: Some(Keycode::H), repeat: false, ..} =>
+            {
+                self.toggle_hilbert();
+            },
+            Event::KeyDown{keycode: Some(Keycode::Right), ..} => self.step_frame(true),
+            Event::KeyDown{keycode: Some(Keycode::Left), ..} => self.step_frame(false),
+            Event::KeyDown{keycode: Some(Keycode::Space), repeat: false, ..} =>
+            {
+                self.paused = !self.paused;
+
+                // resuming waits a whole frame before moving on
+                self.frame_start = Instant::now();
+            },
+            Event::MouseButtonDown{..} => self.clicking = true,
+            // only a click thats released without dragging inspects, pans stay quiet
+            Event::MouseButtonUp{x, y, ..} if self.clicking =>
+            {
+                self.clicking = false;
+
+                self.inspect(Pos2{x, y});
+            },
+            Event::MouseMotion{mousestate, xrel, yrel, ..}
+                if mousestate.pressed_mouse_buttons().next().is_some() =>
+            {
+                self.clicking = false;
+
+                self.pan(Pos2{x: xrel, y: yrel});
+            },
+            _ => ()
+        }
+
+        true
+    }
+
+    fn step_frame(&mut self, forward: bool)
+    {
+        let Some(count) = self.frames.as_ref().map(|frames| frames.count) else
+        {
+            return;
+        };
+
+        let index = if forward { (self.frame + 1) % count } else { (self.frame + count - 1) % count };
+
+        self.show_frame(index);
+    }
+
+    // swaps in another frame, remapped the same way as the current one
+    fn show_frame(&mut self, index: usize)
+    {
+        let Some(frames) = &self.frames else
+        {
+            return;
+        };
+
+        self.frame_start = Instant::now();
+
+        let result = (frames.load)(index).and_then(|mut image|
+        {
+            if self.hilbertified
+            {
+                image.hilbertify(self.curve)?;
+            }
+
+            Ok(image)
+        });
+
+        match result
+        {
+            Ok(image) =>
+            {
+                self.images = vec![image];
+                self.previews = Self::make_previews(&self.images, self.preview_max);
+
+                self.frame = index;
+
+                self.update_title();
+                self.draw();
+            },
+            Err(err) =>
+            {
+                eprintln!("error loading frame {index}, pausing playback: {err}");
+
+                self.paused = true;
+            }
+        }
+    }
+
+    #[allow(clippy::suspicious_else_formatting)]
+    pub fn wait_exit(mut self)
+    {
+        loop
+        {
+            // sleeps until something happens, only animations and frames need to wake up on time
+            let event = if self.animation.is_some()
+            {
+                self.events.wait_event_timeout(self.frame_time.as_millis() as u32)
+            } else if self.frames.is_some() && !self.paused
+            {
+                let remaining = self.frame_time.saturating_sub(self.frame_start.elapsed());
+
+                self.events.wait_event_timeout(remaining.as_millis() as u32)
+            } else
+            {
+                Some(self.events.wait_event())
+            };
+
+            let events = event.into_iter().chain(self.events.poll_iter()).collect::<Vec<_>>();
+            for event in events
+            {
+                if !self.handle_event(event)
+                {
+                    return;
+                }
+            }
+
+            if let Some(animation) = &self.animation
+            {
+                if self.animate.is_none_or(|duration| animation.start.elapsed() >= duration)
+                {
+                    self.animation = None;
+                }
+
+                self.draw();
+            }
+
+            // the curve animation would get cut off by a new frame
+            let playing = self.frames.is_some() && !self.paused && self.animation.is_none();
+            if playing && self.frame_start.elapsed() >= self.frame_time
+            {
+                self.step_frame(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use imagedisplay::image::PixelFormat;
+
+    fn test_image(width: usize, height: usize) -> Image
+    {
+        Image::new(width, height, PixelFormat::Rgb, Color::RGB(0, 0, 0))
+    }
+
+    fn size(image: &Image) -> Pos2<usize>
+    {
+        DrawerWindow::layout_size(std::slice::from_ref(image), 1)
+    }
+
+    #[test]
+    fn frame_titles()
+    {
+        let images = [test_image(4, 2)];
+
+        let title = DrawerWindow::title(&images, CurveType::Hilbert, None);
+        assert!(!title.contains("frame"));
+
+        let title = DrawerWindow::title(&images, CurveType::Hilbert, Some((2, 10)));
+        assert!(title.starts_with("4x2 (8 px, 24 bytes)"));
+        assert!(title.ends_with(" - frame 3/10"));
+    }
+
+    #[test]
+    fn fit_scales()
+    {
+        let display = Pos2{x: 1920, y: 1080};
+
+        assert_eq!(DrawerWindow::fit_scale(size(&test_image(100, 100)), 4, display), (4, 1));
+        assert_eq!(DrawerWindow::fit_scale(size(&test_image(500, 100)), 4, display), (3, 1));
+        assert_eq!(DrawerWindow::fit_scale(size(&test_image(4096, 1024)), 1, display), (1, 3));
+
+        assert_eq!(DrawerWindow::largest_fit(size(&test_image(100, 50)), Pos2{x: 450, y: 400}), (4, 1));
+
+        // wide pixels take up twice the room horizontally
+        let wide = stretched(size(&test_image(500, 100)), Pos2{x: 2, y: 1});
+        assert_eq!(wide, Pos2{x: 1000, y: 100});
+        assert_eq!(DrawerWindow::fit_scale(wide, 4, display), (1, 1));
+    }
+
+    #[test]
+    fn checker_splits()
+    {
+        let cells = checker_cells(Pos2{x: 6, y: 0}, Pos2{x: 4, y: 3}, 8);
+
+        assert_eq!(cells, [
+            (Pos2{x: 6, y: 0}, Pos2{x: 2, y: 3}, true),
+            (Pos2{x: 8, y: 0}, Pos2{x: 2, y: 3}, false)
+        ]);
+
+        // negative positions when the image is panned past the window edge
+        let cells = checker_cells(Pos2{x: -2, y: -2}, Pos2{x: 4, y: 4}, 2);
+
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells.iter().filter(|(_, _, light)| *light).count(), 2);
+        assert_eq!(cells[0], (Pos2{x: -2, y: -2}, Pos2{x: 2, y: 2}, true));
+
+        let area: i32 = checker_cells(Pos2{x: 3, y: 5}, Pos2{x: 17, y: 9}, 4).iter().map(|(_, size, _)| size.x * size.y).sum();
+        assert_eq!(area, 17 * 9);
+    }
+
+    #[test]
+    fn animation_sources()
+    {
+        let mut image = test_image(8, 8);
+        (0..image.data.len()).for_each(|index| image.data[index] = Color::RGB(index as u8, 0, 0));
+
+        let original = image.data.clone();
+
+        image.hilbertify(CurveType::Hilbert).unwrap();
+
+        let sources = DrawerWindow::animation_sources(&image, CurveType::Hilbert, true).unwrap();
+        sources.iter().enumerate().for_each(|(index, source)| assert_eq!(image.data[index], original[*source]));
+
+        let hilbertified = image.data.clone();
+
+        image.unhilbertify(CurveType::Hilbert).unwrap();
+
+        let sources = DrawerWindow::animation_sources(&image, CurveType::Hilbert, false).unwrap();
+        sources.iter().enumerate().for_each(|(index, source)| assert_eq!(image.data[index], hilbertified[*source]));
+    }
+
+    #[test]
+    fn side_by_side_layout()
+    {
+        let images = [test_image(100, 50), test_image(30, 80)];
+
+        assert_eq!(DrawerWindow::layout_size(&images, 1), Pos2{x: 131, y: 80});
+        assert_eq!(DrawerWindow::layout_size(&images, 2), Pos2{x: 66, y: 40});
+
+        assert_eq!(DrawerWindow::locate(&images, 1, 99), Some((0, 99)));
+        assert_eq!(DrawerWindow::locate(&images, 1, 100), None);
+        assert_eq!(DrawerWindow::locate(&images, 1, 101), Some((1, 0)));
+        assert_eq!(DrawerWindow::locate(&images, 1, 131), None);
+        assert_eq!(DrawerWindow::locate(&images, 2, 51), Some((1, 0)));
+    }
+}